@@ -0,0 +1,186 @@
+//! Multi-scale constructions: each square is replaced by its images under a
+//! list of maps, each with its own ratio and offset, instead of equal cells.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Maps the unit square onto the square of side `ratio` at `offset`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Map {
+    pub ratio: f64,
+    pub offset: [f64; 2],
+}
+
+impl Map {
+    /// Position of `point` in the image square, if it falls inside.
+    pub fn inverse(&self, [x, y]: [f64; 2]) -> Option<[f64; 2]> {
+        let [u, v] = [(x - self.offset[0]) / self.ratio, (y - self.offset[1]) / self.ratio];
+        ((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)).then_some([u, v])
+    }
+}
+
+/// Written `ratio@x,y`, for example `0.25@0.75,0`.
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{},{}", self.ratio, self.offset[0], self.offset[1])
+    }
+}
+
+impl FromStr for Map {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (ratio, offset) = value
+            .trim()
+            .split_once('@')
+            .ok_or_else(|| format!("application invalide: {} (rapport@x,y)", value))?;
+        Ok(Self {
+            ratio: ratio
+                .trim()
+                .parse()
+                .map_err(|_| format!("rapport invalide: {}", ratio))?,
+            offset: crate::cli::parse_pair(offset)?,
+        })
+    }
+}
+
+/// List of maps; empty means the rule of the project is used.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Maps(pub Vec<Map>);
+
+impl Maps {
+    /// Two cells of ratios 1/4 and 1/2 on the diagonal, and two more of 1/4
+    /// in the other corners.
+    pub fn example() -> Self {
+        Self(vec![
+            Map { ratio: 0.5, offset: [0.0, 0.0] },
+            Map { ratio: 0.25, offset: [0.75, 0.0] },
+            Map { ratio: 0.25, offset: [0.0, 0.75] },
+            Map { ratio: 0.25, offset: [0.75, 0.75] },
+        ])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Every image must be a smaller square inside the unit square.
+    pub fn validate(&self) -> Result<(), String> {
+        for map in &self.0 {
+            if !(map.ratio > 0.0 && map.ratio < 1.0) {
+                return Err(format!("rapport hors de ]0, 1[: {}", map));
+            }
+            if map.offset.iter().any(|&o| !(o >= 0.0 && o + map.ratio <= 1.0 + 1e-9)) {
+                return Err(format!("image hors du carré unité: {}", map));
+            }
+        }
+        Ok(())
+    }
+
+    /// Similarity dimension: the `s` solving the Moran equation
+    /// `Σ ratioᵢ^s = 1`, found by bisection. It is the Hausdorff dimension
+    /// when the images do not overlap.
+    pub fn dimension(&self) -> f64 {
+        if self.0.len() <= 1 {
+            return 0.0;
+        }
+        let sum = |s: f64| self.0.iter().map(|map| map.ratio.powf(s)).sum::<f64>();
+        // The sum decreases from the number of maps at s = 0 towards 0
+        let (mut low, mut high) = (0.0, 1.0);
+        while sum(high) > 1.0 {
+            low = high;
+            high *= 2.0;
+        }
+        for _ in 0..100 {
+            let mid = (low + high) / 2.0;
+            if sum(mid) > 1.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// Whether the squares of side `side` are subdivided again: not when one
+    /// of their images would be smaller than `pixel`.
+    pub fn subdivides(&self, side: f64, pixel: f64) -> bool {
+        self.0.iter().all(|map| side * map.ratio >= pixel)
+    }
+
+    /// Calls `visit(x, y, side, path)` for each square in a `size × size`
+    /// square at `(x, y)`, like [`crate::rule::Rule::squares`]: `path` holds
+    /// the index of the map chosen at each level. Squares are subdivided while
+    /// their images stay at least one pixel wide.
+    pub fn squares(
+        &self,
+        x: u32,
+        y: u32,
+        size: u32,
+        iterations: u32,
+        visit: &mut impl FnMut(u32, u32, u32, &[u32]),
+    ) {
+        if size == 0 || self.is_empty() {
+            return;
+        }
+        let mut path = Vec::with_capacity(iterations as usize);
+        self.visit_squares([0.0, 0.0], size as f64, iterations, &mut path, &mut |[fx, fy], side, path| {
+            // Rounded edges so that neighbouring squares tile exactly
+            let (x0, y0) = (fx.round(), fy.round());
+            let side = ((fx + side).round() - x0).max(1.0);
+            visit(x + x0 as u32, y + y0 as u32, side as u32, path);
+        });
+    }
+
+    fn visit_squares(
+        &self,
+        corner: [f64; 2],
+        side: f64,
+        iterations: u32,
+        path: &mut Vec<u32>,
+        visit: &mut impl FnMut([f64; 2], f64, &[u32]),
+    ) {
+        if iterations == 0 || !self.subdivides(side, 1.0) {
+            visit(corner, side, path);
+            return;
+        }
+        for (index, map) in self.0.iter().enumerate() {
+            path.push(index as u32);
+            self.visit_squares(
+                [corner[0] + map.offset[0] * side, corner[1] + map.offset[1] * side],
+                side * map.ratio,
+                iterations - 1,
+                path,
+                visit,
+            );
+            path.pop();
+        }
+    }
+}
+
+/// Written as the maps separated by `;`: `0.5@0,0;0.25@0.75,0`.
+impl fmt::Display for Maps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let maps: Vec<String> = self.0.iter().map(Map::to_string).collect();
+        f.write_str(&maps.join(";"))
+    }
+}
+
+impl FromStr for Maps {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let maps = Maps(
+            value
+                .split(';')
+                .filter(|part| !part.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        );
+        maps.validate()?;
+        Ok(maps)
+    }
+}
@@ -0,0 +1,281 @@
+//! Animation writers shared by the animated binaries.
+//!
+//! Frames are produced as RGBA images; the writer is chosen from the file
+//! extension, so every animator supports every format.
+
+use crate::metadata;
+use crate::timing::Ticks;
+use crate::video::{self, VideoOptions};
+use gif::{AnyExtension, Encoder, Extension, Frame, Repeat};
+use image::codecs::webp::WebPEncoder;
+use image::{ColorType, ImageEncoder, RgbaImage};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub trait FrameWriter {
+    /// Appends a frame shown for `delay_ms` milliseconds.
+    ///
+    /// Writers round the running total to their own time unit, so fractional
+    /// delays (33.3 ms for 30 fps) keep the right overall duration.
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>>;
+
+    /// Flushes the output once the last frame is written.
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AnimationOptions {
+    /// Number of plays, 0 for an infinite loop (ignored by videos).
+    pub plays: u32,
+    pub video: VideoOptions,
+    /// Text entries stored in GIF and APNG files (ignored by WebP and videos).
+    pub metadata: metadata::Entries,
+}
+
+/// Opens a writer for `path` based on its extension (`gif`, `png`/`apng`,
+/// `webp`, `mp4`, `webm`). APNG needs the number of `frames` up front.
+pub fn create(
+    path: &str,
+    width: u32,
+    height: u32,
+    frames: u32,
+    options: &AnimationOptions,
+) -> Result<Box<dyn FrameWriter>, Box<dyn Error>> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("gif") => {
+            let mut writer = GifWriter::create(path, width, height, options.plays)?;
+            writer.comment(&options.metadata)?;
+            Ok(Box::new(writer))
+        }
+        Some("png") | Some("apng") => Ok(Box::new(ApngWriter::create(
+            path,
+            width,
+            height,
+            frames,
+            options.plays,
+            &options.metadata,
+        )?)),
+        Some("webp") => Ok(Box::new(WebpWriter::create(path, width, height, options.plays)?)),
+        Some("mp4") | Some("webm") => video::create(path, width, height, &options.video),
+        _ => Err(format!(
+            "format d'animation non reconnu: {} (gif, png, apng, webp, mp4, webm)",
+            path
+        )
+        .into()),
+    }
+}
+
+pub struct GifWriter {
+    encoder: Encoder<File>,
+    width: u16,
+    height: u16,
+    grayscale: bool,
+    ticks: Ticks,
+}
+
+impl GifWriter {
+    /// `plays` is the number of plays, 0 for an infinite loop.
+    pub fn create(path: &str, width: u32, height: u32, plays: u32) -> Result<Self, Box<dyn Error>> {
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(format!("GIF limité à {} pixels de côté", u16::MAX).into());
+        }
+        let file = File::create(path)?;
+        let mut encoder = Encoder::new(file, width as u16, height as u16, &[])?;
+        // The NETSCAPE loop count is the number of repeats after the first play
+        encoder.set_repeat(match plays {
+            0 => Repeat::Infinite,
+            plays => Repeat::Finite((plays - 1).min(u16::MAX as u32) as u16),
+        })?;
+        Ok(Self {
+            encoder,
+            width: width as u16,
+            height: height as u16,
+            grayscale: false,
+            ticks: Ticks::default(),
+        })
+    }
+
+    /// Writes the entries in a comment extension, nothing when there are none.
+    pub fn comment(&mut self, entries: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+        if !entries.is_empty() {
+            let text = metadata::to_text(entries);
+            self.encoder
+                .write_raw_extension(AnyExtension(Extension::Comment as u8), &[text.as_bytes()])?;
+        }
+        Ok(())
+    }
+
+    /// Uses a fixed 256-level gray palette instead of quantizing each frame.
+    pub fn grayscale(mut self) -> Self {
+        self.grayscale = true;
+        self
+    }
+}
+
+impl FrameWriter for GifWriter {
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>> {
+        let mut gif_frame = if self.grayscale {
+            let gray: Vec<u8> = (0..=255u8).flat_map(|level| [level, level, level]).collect();
+            let indexed: Vec<u8> = frame.pixels().map(|pixel| pixel[0]).collect();
+            Frame::from_palette_pixels(self.width, self.height, indexed, gray, None)
+        } else {
+            // Fully transparent pixels become the GIF transparent color
+            let mut rgba = frame.as_raw().clone();
+            Frame::from_rgba_speed(self.width, self.height, &mut rgba, 1)
+        };
+        // GIF delay is in units of 10ms
+        gif_frame.delay = self.ticks.advance(delay_ms, 10.0).min(u16::MAX as u64) as u16;
+        self.encoder.write_frame(&gif_frame)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.encoder.into_inner()?;
+        Ok(())
+    }
+}
+
+/// Animated PNG: lossless, full RGBA.
+pub struct ApngWriter {
+    writer: png::Writer<BufWriter<File>>,
+    ticks: Ticks,
+}
+
+impl ApngWriter {
+    /// `metadata` entries are written as text chunks.
+    pub fn create(
+        path: &str,
+        width: u32,
+        height: u32,
+        frames: u32,
+        plays: u32,
+        metadata: &[(String, String)],
+    ) -> Result<Self, Box<dyn Error>> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        metadata::add_png_text(&mut encoder, metadata)?;
+        // 0 plays = infinite loop
+        encoder.set_animated(frames.max(1), plays)?;
+        let mut writer = encoder.write_header()?;
+        // Each frame replaces the previous one, alpha included
+        writer.set_blend_op(png::BlendOp::Source)?;
+        writer.set_dispose_op(png::DisposeOp::None)?;
+        Ok(Self {
+            writer,
+            ticks: Ticks::default(),
+        })
+    }
+}
+
+impl FrameWriter for ApngWriter {
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>> {
+        let delay = self.ticks.advance(delay_ms, 1.0);
+        // Long holds fall back to centiseconds to fit the 16-bit numerator
+        if delay <= u16::MAX as u64 {
+            self.writer.set_frame_delay(delay as u16, 1000)?;
+        } else {
+            self.writer
+                .set_frame_delay((delay / 10).min(u16::MAX as u64) as u16, 100)?;
+        }
+        self.writer.write_image_data(frame.as_raw())?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Animated WebP built from lossless VP8L frames.
+///
+/// Each frame is encoded as a still lossless WebP, whose `VP8L` chunk is then
+/// wrapped in an `ANMF` chunk. Sizes are patched in [`FrameWriter::finish`].
+pub struct WebpWriter {
+    file: BufWriter<File>,
+    width: u32,
+    height: u32,
+    ticks: Ticks,
+}
+
+impl WebpWriter {
+    /// `plays` is the number of plays, 0 for an infinite loop.
+    pub fn create(path: &str, width: u32, height: u32, plays: u32) -> Result<Self, Box<dyn Error>> {
+        if width == 0 || height == 0 || width > 1 << 24 || height > 1 << 24 {
+            return Err(format!("dimensions WebP invalides: {}×{}", width, height).into());
+        }
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(b"RIFF")?;
+        file.write_all(&0u32.to_le_bytes())?; // patched in finish
+        file.write_all(b"WEBP")?;
+
+        // VP8X: animation (0x02) and alpha (0x10) flags, canvas size - 1
+        file.write_all(b"VP8X")?;
+        file.write_all(&10u32.to_le_bytes())?;
+        file.write_all(&[0x12, 0, 0, 0])?;
+        file.write_all(&(width - 1).to_le_bytes()[..3])?;
+        file.write_all(&(height - 1).to_le_bytes()[..3])?;
+
+        // ANIM: transparent background, loop count (0 = infinite)
+        file.write_all(b"ANIM")?;
+        file.write_all(&6u32.to_le_bytes())?;
+        file.write_all(&[0, 0, 0, 0])?;
+        file.write_all(&(plays.min(u16::MAX as u32) as u16).to_le_bytes())?;
+
+        Ok(Self {
+            file,
+            width,
+            height,
+            ticks: Ticks::default(),
+        })
+    }
+}
+
+impl FrameWriter for WebpWriter {
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>> {
+        let delay = self.ticks.advance(delay_ms, 1.0).min(0xFF_FFFF) as u32;
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still).write_image(
+            frame.as_raw(),
+            frame.width(),
+            frame.height(),
+            ColorType::Rgba8,
+        )?;
+
+        // Skip the RIFF header (12 bytes) to keep only the VP8L chunk
+        let vp8l = still.get(12..).filter(|chunk| chunk.starts_with(b"VP8L")).ok_or("chunk VP8L introuvable")?;
+        let padding = vp8l.len() % 2;
+
+        self.file.write_all(b"ANMF")?;
+        self.file.write_all(&((16 + vp8l.len() + padding) as u32).to_le_bytes())?;
+        self.file.write_all(&[0; 6])?; // frame offset x/2, y/2
+        self.file.write_all(&(self.width - 1).to_le_bytes()[..3])?;
+        self.file.write_all(&(self.height - 1).to_le_bytes()[..3])?;
+        self.file.write_all(&delay.to_le_bytes()[..3])?;
+        // Do not blend with the previous frame, do not dispose
+        self.file.write_all(&[0x02])?;
+        self.file.write_all(vp8l)?;
+        if padding == 1 {
+            self.file.write_all(&[0])?;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        let mut file = self.file.into_inner().map_err(|err| err.into_error())?;
+        let size = file.stream_position()? - 8;
+        file.seek(SeekFrom::Start(4))?;
+        file.write_all(&(size as u32).to_le_bytes())?;
+        Ok(())
+    }
+}
@@ -1,60 +1,9 @@
-use image::{ImageBuffer, Rgba, RgbaImage};
+use fractal_generation_rust::project::Project;
+use fractal_generation_rust::rule::{self, Rule};
+use fractal_generation_rust::{metadata, stats};
+use image::{Rgba, RgbaImage};
 use std::io::{self, Write};
 
-fn draw_cantor_square(
-    image: &mut RgbaImage,
-    x: u32,
-    y: u32,
-    size: u32,
-    iterations: u32,
-    color: Rgba<u8>,
-) {
-    if size == 0 {
-        return;
-    }
-
-    if iterations == 0 {
-        // Draw the square
-        draw_filled_rectangle(image, x, y, size, color);
-        return;
-    }
-
-    let sub_size = size / 3;
-
-    // Draw only 4 corner squares (0,0), (0,2), (2,0), (2,2)
-    for i in [0, 2].iter() {
-        for j in [0, 2].iter() {
-            draw_cantor_square(
-                    image,
-                    x + i * sub_size,
-                    y + j * sub_size,
-                    sub_size,
-                    iterations - 1,
-                    color,
-                );
-            }
-        }
-    }
-
-
-fn draw_filled_rectangle(
-    image: &mut RgbaImage,
-    x: u32,
-    y: u32,
-    size: u32,
-    color: Rgba<u8>,
-) {
-    for i in 0..size {
-        for j in 0..size {
-            let px = x + i;
-            let py = y + j;
-            if px < image.width() && py < image.height() {
-                image.put_pixel(px, py, color);
-            }
-        }
-    }
-}
-
 fn main() {
     println!("=== Générateur de Carré de Cantor ===\n");
 
@@ -70,7 +19,12 @@ fn main() {
     io::stdout().flush().unwrap();
     input.clear();
     io::stdin().read_line(&mut input).unwrap();
-    let iterations: u32 = input.trim().parse().unwrap_or(4);
+    let mut iterations: u32 = input.trim().parse().unwrap_or(4);
+    let depth = rule::depth_limit(3, size as f64);
+    if iterations > depth {
+        println!("Limité à {} itérations : au-delà, les carrés font moins d'un pixel.", depth);
+        iterations = depth;
+    }
 
     // Get output filename
     print!("Nom du fichier de sortie [par défaut: cantor.png]: ");
@@ -90,12 +44,17 @@ fn main() {
 
     // Draw the Cantor square
     println!("\nGénération du carré de Cantor...");
-    draw_cantor_square(&mut image, 0, 0, size, iterations, color);
+    Rule::cantor().draw(&mut image, 0, 0, size, iterations, color);
 
-    // Save the image
-    image.save(filename).expect("Erreur lors de la sauvegarde");
+    // Save the image, with the parameters of the same drawing as a project
+    let project = Project {
+        iterations,
+        palette: vec![color.0],
+        ..Project::default()
+    };
+    metadata::save_image(&image, filename, &project.metadata()).expect("Erreur lors de la sauvegarde");
     println!("✓ Image sauvegardée: {}", filename);
     println!("  - Taille: {} × {} pixels", size, size);
     println!("  - Itérations: {}", iterations);
-    println!("  - Carrés générés: 4^{} = {}", iterations, 4_u32.pow(iterations));
+    println!("  - Carrés générés: 4^{} = {}", iterations, stats::power_label(4, iterations));
 }
@@ -0,0 +1,692 @@
+use fractal_generation_rust::affine::Maps;
+use fractal_generation_rust::animation::{self, AnimationOptions};
+use fractal_generation_rust::box_counting;
+use fractal_generation_rust::cli::{self, Args};
+use fractal_generation_rust::dust::{Dust, Selection};
+use fractal_generation_rust::ifs::{self, Ifs, Mode};
+use fractal_generation_rust::mesh::Mesh;
+use fractal_generation_rust::metadata;
+use fractal_generation_rust::project::Project;
+use fractal_generation_rust::randomize::{self, Seed};
+use fractal_generation_rust::raycast::{Orbit, Scene, Shading};
+use fractal_generation_rust::rule::{self, Rule};
+use fractal_generation_rust::server::{self, ServerOptions};
+use fractal_generation_rust::solid::{MAX_MESH_CUBES, SolidRule};
+use fractal_generation_rust::stats::{self, Measures, PixelStats};
+use fractal_generation_rust::tiles;
+use fractal_generation_rust::video;
+use image::{Rgba, RgbaImage};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: fractal <commande> [options]
+
+Commandes:
+  stats     mesures théoriques et comptage des pixels d'un rendu
+            --iterations <n>   itérations (défaut: 4)
+            --rule <règle>     règle de subdivision (défaut: 3:101000101)
+            --maps <r@x,y;...> homothéties multi-échelles à la place de la règle,
+                               ex. 0.5@0,0;0.25@0.75,0;0.25@0,0.75
+            --size <px>        côté du rendu compté (défaut: 729)
+  dimension [image]
+            estimation de la dimension par comptage de boîtes, sur une
+            image chargée ou, sans image, sur un rendu (mêmes options que stats)
+            --background <c>   couleur du fond (défaut: 255,255,255)
+            --base <n>         rapport entre deux tailles de boîtes (défaut: la
+                               grille de la règle pour un rendu, 2 pour une image)
+            --min-box <px>     plus petite boîte (défaut: les carrés de la dernière
+                               itération pour un rendu, 1 pour une image)
+            --csv <fichier>    points de mesure en CSV
+            --plot <fichier>   graphique log-log en SVG
+  dust      poussière de Cantor aléatoire : comptes et dimension estimée
+            comparés aux valeurs attendues
+            --grid <n>         cases par côté (défaut: 3)
+            --dust <tirage>    p=<proba> par case, ou k=<n> cases par carré (défaut: p=0.6)
+            --iterations <n>   itérations (défaut: 5)
+            --seed <n>         graine du premier tirage (défaut: au hasard)
+            --runs <n>         tirages, graines successives (défaut: 1)
+            --size <px>        côté du rendu mesuré (défaut: 729)
+            --image <fichier>  rendu du premier tirage
+  ifs [système.toml]
+            système de fonctions itérées, chargé d'un fichier ou de la galerie
+            --preset <nom>     cantor, sierpinski, fougere ou dragon (défaut: fougere)
+            --rule <règle>     système du carré de Cantor d'une règle
+            --maps <r@x,y;...> système des homothéties multi-échelles
+            --mode <mode>      chaos ou deterministe (défaut: chaos)
+            --points <n>       points du jeu du chaos (défaut: 2000000)
+            --iterations <n>   profondeur du mode déterministe (défaut: 8)
+            --seed <n>         graine du jeu du chaos (défaut: 0)
+            --size <px>        côté de l'image (défaut: 1024)
+            --gamma <g>        correction des densités (défaut: 2.2)
+            --fill <couleur>   couleur des zones denses (défaut: 0,0,0)
+            --image <fichier>  image (défaut: ifs.png)
+            --save <fichier>   enregistre le système en TOML
+  mesh      poussière de Cantor 3D ou éponge de Menger en maillage, sans les
+            faces internes partagées par deux cubes
+            --rule <règle>     menger, dust ou grille:masque de grille³ cases
+                               (défaut: menger)
+            --iterations <n>   itérations, au plus 2²⁰ cubes (défaut: 3)
+            --size <mm>        côté du cube entier (défaut: 100)
+            --output <fichier> maillage .stl, .obj ou .ply (défaut: menger.stl)
+  preview   aperçu 3D lancé de rayons (mêmes règles que mesh), image fixe
+            ou tour complet autour du cube
+            --rule <règle>     menger, dust ou grille:masque (défaut: menger)
+            --iterations <n>   itérations (défaut: 3)
+            --size <px>        côté de l'image (défaut: 512)
+            --yaw <deg>        angle autour de l'axe vertical (défaut: 35)
+            --pitch <deg>      hauteur de la caméra (défaut: 25)
+            --distance <d>     distance au centre du cube (défaut: 2.6)
+            --color <couleur>  couleur du solide (défaut: 239,71,111)
+            --ao <n>           rayons d'occlusion ambiante, 0 = aucune (défaut: 8)
+            --no-shadows       sans ombres portées
+            --image <fichier>  image fixe (défaut: preview.png)
+            --animation <fichier>  tour complet (gif, png, webp, mp4, webm)
+            --frames <n>       images du tour (défaut: 36)
+            --delay <ms>       durée d'une image (défaut: 80)
+  render <projet.toml>
+            rendu d'un projet enregistré depuis l'interface
+            --image <fichier>      image fixe, format d'après l'extension : png, jpg,
+                                   tif, bmp, webp, qoi, ppm ou pgm (défaut: cantor.png)
+            --jpeg-quality <q>     qualité JPEG de 1 à 100 (défaut: celle du projet, 75)
+            --png-compression <c>  fast, default ou best
+            --16-bit               16 bits par canal (PNG, TIFF)
+            --no-alpha             sans canal alpha, couleurs posées sur du blanc
+            --svg <fichier>        image vectorielle
+            --animation <fichier>  animation du chemin de caméra (gif, png, webp, mp4, webm)
+            --size <px>            côté des images fixes (défaut: celui du projet)
+  tiles <projet.toml>
+            très grandes images rendues par morceaux, en mémoire bornée
+            --size <px>            côté de l'image (défaut: 59049)
+            --output <fichier>     PNG, BigTIFF (.tif) ou pyramide Deep Zoom (.dzi)
+                                   (défaut: cantor.tif)
+            --xyz <dossier>        pyramide de tuiles {z}/{x}/{y}.png avec une
+                                   page index.html pour la parcourir hors ligne
+            --levels <n>           dernier niveau de --xyz (défaut: d'après --size)
+            --iterations <n>       itérations (défaut: jusqu'aux carrés d'un pixel)
+  serve <projet.toml>
+            serveur local de tuiles {z}/{x}/{y}.png rendues à la demande,
+            avec une page pour zoomer dans le navigateur
+            --port <n>             port sur 127.0.0.1 (défaut: 8080)
+            --max-zoom <n>         niveau le plus profond (défaut: 32)
+            --iterations <n>       itérations (défaut: jusqu'aux carrés d'un pixel)
+            --cache <dossier>      tuiles déjà rendues (défaut: .fracantor-cache)
+            --no-cache             rend chaque tuile à nouveau
+  inspect <fichier>
+            paramètres enregistrés dans un export (PNG, SVG, GIF, JPEG),
+            et nouveau rendu avec les options de render (défaut: image
+                                   fixe <nom>.inspect.png à côté du fichier)
+            --project <fichier>    enregistre les paramètres comme projet";
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::from_env();
+    match args.positional().first().map(String::as_str) {
+        Some("stats") => stats(&args),
+        Some("dimension") => dimension(&args),
+        Some("dust") => dust(&args),
+        Some("ifs") => ifs(&args),
+        Some("mesh") => mesh(&args),
+        Some("preview") => preview(&args),
+        Some("render") => render_project(&args),
+        Some("tiles") => render_tiles(&args),
+        Some("serve") => serve(&args),
+        Some("inspect") => inspect(&args),
+        Some(command) => Err(format!("commande inconnue: {} (voir fractal --help)", command).into()),
+        None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn stats(args: &Args) -> Result<(), Box<dyn Error>> {
+    let iterations: u32 = args.get("iterations")?.unwrap_or(4);
+    let rule: Rule = args.get_with("rule", str::parse)?.unwrap_or_default();
+    let size: u32 = args.get("size")?.unwrap_or(729);
+    if let Some(maps) = args.get_with("maps", str::parse)? {
+        return maps_stats(&maps, iterations, size);
+    }
+
+    let measures = Measures::new(&rule, iterations);
+    let (grid, kept) = (rule.grid, rule.kept());
+
+    println!("=== Statistiques ===\n");
+    println!("Règle: {} ({} cases gardées sur {}×{})", rule, kept, grid, grid);
+    println!("Itérations: {}", iterations);
+    match measures.squares {
+        Some(squares) => println!("Carrés: {}^{} = {}", kept, iterations, squares),
+        None => println!("Carrés: {}", stats::power_label(kept, iterations)),
+    }
+    println!("Côté d'un carré: {}^-{} = {:.6e}", grid, iterations, measures.side);
+    println!(
+        "Aire restante: ({}/{})^{} = {:.6e} ({:.4} %)",
+        kept,
+        grid * grid,
+        iterations,
+        measures.area,
+        measures.area * 100.0
+    );
+    println!("Périmètre: {:.6}", measures.perimeter);
+    println!(
+        "Dimension de Hausdorff: log {} / log {} = {:.6}",
+        kept, grid, measures.dimension
+    );
+
+    let image = render(&rule, iterations, size);
+    let pixels = PixelStats::count(&image, WHITE);
+
+    println!("\nRendu {} × {} pixels:", size, size);
+    println!(
+        "  Pixels remplis: {} / {} ({:.4} %)",
+        pixels.filled,
+        pixels.total,
+        pixels.fraction() * 100.0
+    );
+    if iterations > rule.max_depth(size) {
+        println!(
+            "  (rendu limité à {} itérations : au-delà, les carrés font moins d'un pixel)",
+            rule.max_depth(size)
+        );
+    } else if grid.checked_pow(iterations).is_none_or(|cells| !size.is_multiple_of(cells)) {
+        println!(
+            "  (le côté n'est pas divisible par {}^{}: les carrés sont arrondis)",
+            grid, iterations
+        );
+    }
+
+    Ok(())
+}
+
+fn maps_stats(maps: &Maps, iterations: u32, size: u32) -> Result<(), Box<dyn Error>> {
+    let measures = Measures::from_maps(maps, iterations);
+    let ratios: Vec<String> = maps.0.iter().map(|map| map.ratio.to_string()).collect();
+
+    println!("=== Statistiques ===\n");
+    println!("Homothéties: {}", maps);
+    println!("Itérations: {}", iterations);
+    println!("Carrés: {}", stats::power_label(maps.0.len() as u32, iterations));
+    println!("Plus petit côté: {:.6e}", measures.side);
+    println!("Aire restante: (Σ rᵢ²)^{} = {:.6e} ({:.4} %)", iterations, measures.area, measures.area * 100.0);
+    println!("Périmètre (arêtes communes comprises): {:.6}", measures.perimeter);
+    println!(
+        "Dimension de similitude: Σ rᵢ^s = 1 pour r = {} → s = {:.6}",
+        ratios.join(", "),
+        measures.dimension
+    );
+
+    let project = Project {
+        maps: maps.clone(),
+        iterations,
+        palette: vec![[0, 0, 0, 255]],
+        ..Project::default()
+    };
+    let pixels = PixelStats::count(&project.render_image(size), WHITE);
+    println!("\nRendu {} × {} pixels:", size, size);
+    println!(
+        "  Pixels remplis: {} / {} ({:.4} %)",
+        pixels.filled,
+        pixels.total,
+        pixels.fraction() * 100.0
+    );
+    println!("  (les carrés ne sont plus subdivisés quand leurs images feraient moins d'un pixel)");
+
+    Ok(())
+}
+
+fn dimension(args: &Args) -> Result<(), Box<dyn Error>> {
+    let background = match args.get_with("background", cli::parse_rgba)? {
+        Some(color) => Rgba(color),
+        None => WHITE,
+    };
+
+    // Boîtes en puissances de la grille pour un rendu, de 2 pour une image
+    let (image, sizes) = match args.positional().get(1) {
+        Some(path) => {
+            println!("Image: {}", path);
+            let image = image::open(path)?.to_rgba8();
+            let base: u32 = args.get("base")?.unwrap_or(2);
+            let min_box: u32 = args.get("min-box")?.unwrap_or(1);
+            let sizes = box_counting::geometric_sizes(image.width(), image.height(), base, min_box);
+            (image, sizes)
+        }
+        None => {
+            let iterations: u32 = args.get("iterations")?.unwrap_or(4);
+            let rule: Rule = args.get_with("rule", str::parse)?.unwrap_or_default();
+            let size: u32 = args.get("size")?.unwrap_or(729);
+            println!(
+                "Rendu: règle {}, {} itérations, {} × {} pixels (dimension théorique {:.6})",
+                rule,
+                iterations,
+                size,
+                size,
+                stats::dimension(&rule)
+            );
+            let sizes = match (args.get("base")?, args.get("min-box")?) {
+                (None, None) => box_counting::construction_sizes(size, rule.grid, iterations),
+                (base, min_box) => {
+                    let depth = iterations.min(rule.max_depth(size));
+                    let smallest = (size / rule.grid.pow(depth)).max(1);
+                    box_counting::geometric_sizes(size, size, base.unwrap_or(rule.grid), min_box.unwrap_or(smallest))
+                }
+            };
+            (render(&rule, iterations, size), sizes)
+        }
+    };
+
+    let estimate = box_counting::estimate(&image, background, &sizes);
+    if estimate.points.len() < 2 {
+        return Err("pas assez de tailles de boîtes pour estimer la dimension".into());
+    }
+
+    println!("\n{:>8} {:>10}", "Taille", "Boîtes");
+    for point in &estimate.points {
+        println!("{:>8} {:>10}", point.size, point.count);
+    }
+    println!(
+        "\nlog N = {:.6} × log(1/s) + {:.6}",
+        estimate.dimension, estimate.intercept
+    );
+    println!("Dimension estimée: {:.6} (R² = {:.6})", estimate.dimension, estimate.r_squared);
+
+    if let Some(path) = args.value("csv") {
+        std::fs::write(path, estimate.to_csv())?;
+        println!("✓ CSV sauvegardé: {}", path);
+    }
+    if let Some(path) = args.value("plot") {
+        svg::save(path, &estimate.plot())?;
+        println!("✓ Graphique sauvegardé: {}", path);
+    }
+
+    Ok(())
+}
+
+fn dust(args: &Args) -> Result<(), Box<dyn Error>> {
+    let grid: u32 = args.get("grid")?.unwrap_or(3);
+    let selection: Selection = args.get_with("dust", str::parse)?.unwrap_or(Selection::Probability(0.6));
+    let iterations: u32 = args.get("iterations")?.unwrap_or(5);
+    let seed: Seed = args.get("seed")?.unwrap_or_else(randomize::new_seed);
+    let runs: u32 = args.get("runs")?.unwrap_or(1).max(1);
+    let size: u32 = args.get("size")?.unwrap_or(729);
+    if grid < 2 {
+        return Err("la grille doit avoir au moins 2 cases par côté".into());
+    }
+    selection.validate(grid)?;
+
+    // La règle ne donne que la grille ; le masque est tiré pour chaque carré
+    let project = |seed| Project {
+        rule: Rule {
+            grid,
+            keep: vec![true; (grid * grid) as usize],
+        },
+        iterations,
+        dust: Some(selection),
+        seed,
+        palette: vec![[0, 0, 0, 255]],
+        ..Project::default()
+    };
+    // Pas de carrés plus petits qu'un pixel, ni pour le rendu ni pour les comptes
+    let depth = iterations.min(rule::depth_limit(grid, size as f64));
+    let model = Dust { grid, selection, seed };
+    let (expected, expected_dimension) = (model.expected_squares(depth), model.expected_dimension());
+
+    println!("=== Poussière aléatoire ===\n");
+    println!("Grille: {}×{}, tirage {}, {} itérations", grid, grid, selection, iterations);
+    if depth < iterations {
+        println!("  (limité à {} itérations : au-delà, les carrés font moins d'un pixel)", depth);
+    }
+    println!("Carrés attendus: {:.3e}", expected);
+    println!("Dimension attendue: {:.6}", expected_dimension);
+    if let Some(filename) = args.value("image") {
+        project(seed).export_image(filename, size)?;
+        println!("✓ Image sauvegardée: {}", filename);
+    }
+    println!("\n{:>12} {:>14} {:>12} {:>12}", "Graine", "Carrés", "Croissance", "Boîtes");
+
+    // Boîtes pas plus petites que les carrés de la dernière itération rendue
+    let sizes = box_counting::construction_sizes(size, grid, iterations);
+    let (mut counts, mut growths, mut estimates, mut extinct) = (Vec::new(), Vec::new(), Vec::new(), 0);
+    for run in 0..runs {
+        let project = project(seed.wrapping_add(run));
+        let dust = project.dust().ok_or("poussière absente")?;
+        let count = dust.count(size, iterations);
+        counts.push(count as f64);
+        if count == 0 {
+            extinct += 1;
+            println!("{:>12} {:>14} {:>12} {:>12}", dust.seed, 0, "éteinte", "-");
+            continue;
+        }
+
+        // Dimension de croissance log N / (n log grille), puis comptage de boîtes du rendu
+        let growth = if depth == 0 { 0.0 } else { (count as f64).ln() / (depth as f64 * (grid as f64).ln()) };
+        let image = project.render_image(size);
+        let estimate = box_counting::estimate(&image, WHITE, &sizes);
+        growths.push(growth);
+        let boxes = if estimate.points.len() >= 2 {
+            estimates.push(estimate.dimension);
+            format!("{:.6}", estimate.dimension)
+        } else {
+            "-".to_string()
+        };
+        println!("{:>12} {:>14} {:>12.6} {:>12}", dust.seed, count, growth, boxes);
+    }
+
+    let (mean, std) = mean_std(&counts);
+    println!("\nCarrés: {:.1} ± {:.1} en moyenne (attendu {:.1})", mean, std, expected);
+    println!("Extinctions: {}/{}", extinct, runs);
+    for (name, values) in [("croissance", &growths), ("boîtes", &estimates)] {
+        if !values.is_empty() {
+            let (mean, std) = mean_std(values);
+            println!(
+                "Dimension ({}): {:.6} ± {:.6}, écart à l'attendue {:+.6}",
+                name,
+                mean,
+                std,
+                mean - expected_dimension
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Mean and standard deviation.
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    let n = values.len().max(1) as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+fn ifs(args: &Args) -> Result<(), Box<dyn Error>> {
+    let system = match (args.positional().get(1), args.value("rule"), args.value("maps")) {
+        (Some(path), _, _) => Ifs::load(path)?,
+        (None, Some(rule), _) => Ifs::from_rule(&rule.parse()?),
+        (None, None, Some(maps)) => Ifs::from_maps(&maps.parse()?),
+        (None, None, None) => ifs::named(args.value("preset").unwrap_or("fougere"))?,
+    };
+    system.validate()?;
+    let mode: Mode = args.get("mode")?.unwrap_or_default();
+    let points: u64 = args.get("points")?.unwrap_or(2_000_000);
+    let iterations: u32 = args.get("iterations")?.unwrap_or(8);
+    let seed: Seed = args.get("seed")?.unwrap_or(0);
+    let size: u32 = args.get("size")?.unwrap_or(1024);
+    let gamma: f64 = args.get("gamma")?.unwrap_or(2.2);
+    let [r, g, b] = args.get_with("fill", cli::parse_rgb)?.unwrap_or([0, 0, 0]);
+    let filename = args.value("image").unwrap_or("ifs.png");
+
+    println!("=== {} ===\n", system.name);
+    let probabilities = system.probabilities();
+    for (i, (map, p)) in system.maps.iter().zip(&probabilities).enumerate() {
+        let [[a, b], [c, d]] = map.matrix;
+        println!(
+            "  {}: [{} {}; {} {}] + ({}, {})  p = {:.3}",
+            i + 1,
+            a,
+            b,
+            c,
+            d,
+            map.offset[0],
+            map.offset[1],
+            p
+        );
+    }
+
+    let histogram = match mode {
+        Mode::Chaos => {
+            println!("\nJeu du chaos: {} points, graine {}", points, seed);
+            system.chaos_game(size, size, points, seed)
+        }
+        Mode::Deterministic => {
+            println!("\nItération déterministe: {} niveaux", iterations);
+            system.deterministic(size, size, iterations)
+        }
+    };
+    let filled = histogram.counts.iter().filter(|&&count| count > 0).count();
+    println!("Pixels atteints: {} / {}", filled, histogram.counts.len());
+
+    let image = histogram.tone_map([255, 255, 255, 255], [r, g, b, 255], gamma);
+    let entries = system.metadata((mode == Mode::Chaos).then_some(seed));
+    metadata::save_image(&image, filename, &entries)?;
+    println!("✓ Image sauvegardée: {}", filename);
+    if let Some(path) = args.value("save") {
+        system.save(path)?;
+        println!("✓ Système sauvegardé: {}", path);
+    }
+
+    Ok(())
+}
+
+fn mesh(args: &Args) -> Result<(), Box<dyn Error>> {
+    let rule: SolidRule = args.get_with("rule", str::parse)?.unwrap_or_default();
+    let iterations: u32 = args.get("iterations")?.unwrap_or(3);
+    let size: f32 = args.get("size")?.unwrap_or(100.0);
+    let output = args.value("output").unwrap_or("menger.stl");
+    // Au-delà de 729 cubes par côté, la grille de voxels ne tient plus en mémoire
+    let depth = iterations.min(rule.depth_limit(729));
+    let budget = rule.cube_limit(MAX_MESH_CUBES);
+    if depth > budget {
+        return Err(format!(
+            "{} itérations donneraient {} cubes, le maillage est limité à {} (au plus {} itérations pour {})",
+            depth,
+            (rule.kept() as u64).saturating_pow(depth),
+            MAX_MESH_CUBES,
+            budget,
+            rule
+        )
+        .into());
+    }
+
+    println!("=== Maillage ===\n");
+    println!("Règle: {} ({} cubes gardés sur {}³)", rule, rule.kept(), rule.grid);
+    println!("Dimension de Hausdorff: {:.6}", rule.dimension());
+    if depth < iterations {
+        println!("Itérations: {} (limité depuis {})", depth, iterations);
+    } else {
+        println!("Itérations: {}", depth);
+    }
+
+    let (mesh, stats) = Mesh::from_voxels(&rule.voxels(depth), size);
+    println!("Cubes: {}", stats.cubes);
+    println!("Faces internes retirées: {} sur {}", stats.internal_faces, stats.cubes * 6);
+    println!("Sommets: {}, triangles: {}", mesh.vertices.len(), mesh.triangles.len());
+
+    mesh.save(output, &format!("{} n={}", rule, depth))?;
+    println!("✓ Maillage sauvegardé: {}", output);
+    Ok(())
+}
+
+fn preview(args: &Args) -> Result<(), Box<dyn Error>> {
+    let rule: SolidRule = args.get_with("rule", str::parse)?.unwrap_or_default();
+    let iterations: u32 = args.get("iterations")?.unwrap_or(3);
+    let size: u32 = args.get("size")?.unwrap_or(512);
+    let defaults = Orbit::default();
+    let orbit = Orbit {
+        yaw: args.get("yaw")?.unwrap_or(defaults.yaw),
+        pitch: args.get("pitch")?.unwrap_or(defaults.pitch),
+        distance: args.get("distance")?.unwrap_or(defaults.distance),
+        ..defaults
+    };
+    let mut shading = Shading {
+        ao_samples: args.get("ao")?.unwrap_or(8),
+        shadows: !args.flag("no-shadows"),
+        ..Shading::default()
+    };
+    if let Some([r, g, b]) = args.get_with("color", cli::parse_rgb)? {
+        shading.color = [r, g, b, 255];
+    }
+    if size == 0 {
+        return Err("la taille de l'image doit être positive".into());
+    }
+    if orbit.distance <= 0.87 {
+        return Err("la caméra doit rester hors du cube (--distance > 0.87)".into());
+    }
+
+    let scene = Scene::new(&rule, iterations);
+    let mut entries = metadata::generator();
+    entries.extend([
+        metadata::entry(metadata::SOLID, &rule),
+        metadata::entry(metadata::ITERATIONS, iterations),
+    ]);
+
+    if let Some(filename) = args.value("animation") {
+        let frames: u32 = args.get("frames")?.unwrap_or(36).max(1);
+        let delay: f64 = args.get("delay")?.unwrap_or(80.0);
+        let options = AnimationOptions {
+            metadata: entries,
+            ..AnimationOptions::default()
+        };
+        if let Some(notice) = video::fallback_notice(filename) {
+            println!("{}", notice);
+        }
+        let mut writer = animation::create(filename, size, size, frames, &options)?;
+        for frame in 0..frames {
+            println!("Frame {}/{}", frame + 1, frames);
+            writer.write_frame(&scene.render(&orbit.turn(frame, frames), size, size, &shading)?, delay)?;
+        }
+        writer.finish()?;
+        println!("✓ Animation sauvegardée: {}", filename);
+        return Ok(());
+    }
+
+    let filename = args.value("image").unwrap_or("preview.png");
+    metadata::save_image(&scene.render(&orbit, size, size, &shading)?, filename, &entries)?;
+    println!("✓ Image sauvegardée: {}", filename);
+    Ok(())
+}
+
+fn render_project(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args
+        .positional()
+        .get(1)
+        .ok_or("fichier projet manquant (fractal render <projet.toml>)")?;
+    let project = Project::load(path)?;
+
+    // Sans sortie demandée, une image fixe comme le bouton PNG
+    let default_image = args.value("svg").is_none() && args.value("animation").is_none();
+    render_outputs(&project, args, default_image.then_some("cantor.png"))
+}
+
+fn render_tiles(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args
+        .positional()
+        .get(1)
+        .ok_or("fichier projet manquant (fractal tiles <projet.toml>)")?;
+    let mut project = Project::load(path)?;
+    let size: u32 = args.get("size")?.unwrap_or(59049);
+    let levels: u32 = args.get("levels")?.unwrap_or_else(|| tiles::xyz_levels(size));
+    // Les itérations du projet sont réglées pour sa vue fixe : on descend ici
+    // jusqu'aux carrés d'un pixel de l'image ou du dernier niveau
+    let side = match args.value("xyz") {
+        Some(_) => tiles::TILE as f64 * 2f64.powi(levels as i32),
+        None => size as f64,
+    };
+    project.iterations = args.get("iterations")?.unwrap_or_else(|| project.pixel_depth(side));
+
+    if let Some(dir) = args.value("xyz") {
+        tiles::export_xyz(&project, levels, dir, |step| println!("{}", step))?;
+        println!("✓ Pyramide XYZ sauvegardée: {} (ouvrir index.html)", dir);
+        return Ok(());
+    }
+    let filename = args.value("output").unwrap_or("cantor.tif");
+    tiles::export(&project, size, filename, |step| println!("{}", step))?;
+    println!("✓ Image {}×{} sauvegardée: {}", size, size, filename);
+    Ok(())
+}
+
+fn serve(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args
+        .positional()
+        .get(1)
+        .ok_or("fichier projet manquant (fractal serve <projet.toml>)")?;
+    let mut project = Project::load(path)?;
+
+    let defaults = ServerOptions::default();
+    let options = ServerOptions {
+        port: args.get("port")?.unwrap_or(defaults.port),
+        max_zoom: args.get("max-zoom")?.unwrap_or(defaults.max_zoom).min(48),
+        cache: if args.flag("no-cache") {
+            None
+        } else {
+            args.value("cache").map(PathBuf::from).or(defaults.cache)
+        },
+    };
+    // Comme pour fractal tiles : jusqu'aux carrés d'un pixel du niveau le plus profond
+    let side = tiles::TILE as f64 * 2f64.powi(options.max_zoom as i32);
+    project.iterations = args.get("iterations")?.unwrap_or_else(|| project.pixel_depth(side));
+    server::serve(&project, &options)
+}
+
+fn inspect(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args
+        .positional()
+        .get(1)
+        .ok_or("fichier manquant (fractal inspect <fichier>)")?;
+    let entries = metadata::read(path)?;
+
+    println!("=== {} ===\n", path);
+    for (key, value) in &entries {
+        if key == metadata::PROJECT || key == metadata::IFS {
+            println!("{}: {} lignes", key, value.lines().count());
+        } else {
+            println!("{}: {}", key, value);
+        }
+    }
+
+    let project = Project::from_metadata(&entries)?;
+    if let Some(filename) = args.value("project") {
+        project.save(filename)?;
+        println!("✓ Projet sauvegardé: {}", filename);
+    }
+
+    // Sans sortie demandée, la vue fixe est rendue à côté du fichier lu
+    let no_output = ["image", "svg", "animation"].iter().all(|name| args.value(name).is_none());
+    let default_image = Path::new(path).with_extension("inspect.png");
+    let default_image = default_image.to_str().ok_or("chemin non UTF-8")?;
+    render_outputs(&project, args, no_output.then_some(default_image))
+}
+
+/// Writes the `--image`, `--svg` and `--animation` outputs of `project`,
+/// or `default_image` when none is given.
+fn render_outputs(project: &Project, args: &Args, default_image: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut project = project.clone();
+    let options = &mut project.export.image;
+    if let Some(quality) = args.get("jpeg-quality")? {
+        options.jpeg_quality = quality;
+    }
+    if let Some(compression) = args.get("png-compression")? {
+        options.png_compression = compression;
+    }
+    options.sixteen_bit |= args.flag("16-bit");
+    options.alpha &= !args.flag("no-alpha");
+    let project = &project;
+
+    let size: u32 = args.get("size")?.unwrap_or(project.export.image_size);
+    let (svg, animation) = (args.value("svg"), args.value("animation"));
+    let image = args.value("image").or(default_image);
+
+    if let Some(filename) = image {
+        project.export_image(filename, size)?;
+        println!("✓ Image sauvegardée: {}", filename);
+    }
+    if let Some(filename) = svg {
+        svg::save(filename, &project.render_svg(size))?;
+        println!("✓ SVG sauvegardé: {}", filename);
+    }
+    if let Some(filename) = animation {
+        if let Some(notice) = video::fallback_notice(filename) {
+            println!("{}", notice);
+        }
+        project.export_animation(filename, |frame, frames| println!("Frame {}/{}", frame, frames))?;
+        println!("✓ Animation sauvegardée: {}", filename);
+    }
+
+    Ok(())
+}
+
+/// Rule drawn in black on white, in a `size × size` image.
+fn render(rule: &Rule, iterations: u32, size: u32) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(size, size, WHITE);
+    rule.draw(&mut image, 0, 0, size, iterations, Rgba([0, 0, 0, 255]));
+    image
+}
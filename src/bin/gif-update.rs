@@ -1,188 +1,284 @@
-use gif::{Encoder, Frame, Repeat};
-use std::{error::Error, fs::File};
-use rayon::prelude::*;
-
-const WIDTH: usize = 1600;
-const HEIGHT: usize = 1600;
-const NUM_FRAMES: u32 = 90;
-const ZOOM_SPEED: f32 = 1.05;
-const ITERATIONS: u32 = 11;
-
-#[derive(Clone, Copy)]
-struct Square {
-    x: f32,
-    y: f32,
-    side: f32,
-}
+use fractal_generation_rust::affine::Maps;
+use fractal_generation_rust::animation::{self, AnimationOptions};
+use fractal_generation_rust::metadata::{self, entry};
+use fractal_generation_rust::camera::CameraPath;
+use fractal_generation_rust::cli::{self, Args};
+use fractal_generation_rust::pipeline;
+use fractal_generation_rust::render::{self, Coloring, Style};
+use fractal_generation_rust::rule::Rule;
+use fractal_generation_rust::timing::{self, Timing};
+use fractal_generation_rust::video::{self, VideoOptions};
+use serde::Deserialize;
+use std::{error::Error, fs};
+
+const USAGE: &str = "\
+Usage: gif-update [options]
+
+Options:
+  --config <fichier.toml>   charge les paramètres depuis un fichier TOML
+  --width <px>              largeur du GIF (défaut: 1600)
+  --height <px>             hauteur du GIF (défaut: 1600)
+  --frames <n>              nombre de frames (défaut: 90)
+  --zoom-speed <f>          facteur de zoom par frame (défaut: 1.05)
+  --iterations <n>          itérations du carré de Cantor (défaut: 11)
+  --rule <grille:masque>    règle de subdivision (défaut: 3:101000101)
+  --maps <r@x,y;...>        homothéties multi-échelles remplaçant la règle,
+                            ex. 0.5@0,0;0.25@0.75,0;0.25@0,0.75
+  --target <x,y>            point visé par le zoom (défaut: 0.3333,0.3333)
+  --fill <r,g,b|#rrggbb>    couleur de remplissage (défaut: 50,150,255)
+  --border <r,g,b|#rrggbb>  couleur des bordures (défaut: 255,255,255)
+  --background <r,g,b[,a]>  couleur du fond, alpha compris (défaut: 0,0,0,255)
+  --output <fichier>        fichier de sortie .gif, .png (APNG), .webp,
+                            .mp4 ou .webm
+                            (défaut: cantor_zoom.gif)
+  --fps <n>                 images par seconde en vidéo (défaut: 25)
+  --quality <crf>           qualité vidéo, plus bas = meilleur (défaut: 23)
+  --delay <ms|Nfps>         durée d'une frame, ex. 40, 40ms ou 25fps
+                            (défaut: 40)
+  --hold-first <ms>         pause supplémentaire sur la première frame
+  --hold-last <ms>          pause supplémentaire sur la dernière frame
+  --timing <courbe>         répartition des frames dans le temps : linear,
+                            ease-in-out ou exponential (défaut: linear)
+  --plays <n>               nombre de lectures, 0 = en boucle (défaut: 0)
+  --camera <chemin.toml>    chemin de caméra par keyframes (remplace
+                            --zoom-speed et --target)
 
-struct FrameBuffer {
-    pixels: Vec<u8>,
+Les options de la ligne de commande remplacent celles du fichier.";
+
+/// Zoom animation parameters, read from `--config` then overridden by flags.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+    width: usize,
+    height: usize,
+    frames: u32,
+    zoom_speed: f32,
+    iterations: u32,
+    rule: Rule,
+    maps: Maps,
+    target: [f32; 2],
+    fill: [u8; 3],
+    border: [u8; 3],
+    background: [u8; 4],
+    output: String,
+    camera: Option<String>,
+    fps: u32,
+    quality: u32,
+    timing: Timing,
 }
 
-impl FrameBuffer {
-    fn new() -> Self {
+impl Default for Settings {
+    fn default() -> Self {
         Self {
-            pixels: vec![0; WIDTH * HEIGHT * 3],
+            width: 1600,
+            height: 1600,
+            frames: 90,
+            zoom_speed: 1.05,
+            iterations: 11,
+            rule: Rule::cantor(),
+            maps: Maps::default(),
+            target: [1.0 / 3.0, 1.0 / 3.0],
+            fill: [50, 150, 255],
+            border: [255, 255, 255],
+            background: [0, 0, 0, 255],
+            output: "cantor_zoom.gif".to_string(),
+            camera: None,
+            fps: VideoOptions::default().fps,
+            quality: VideoOptions::default().quality,
+            timing: Timing::default(),
         }
     }
+}
 
-    fn draw_at(&mut self, x: usize, y: usize, rgb: (u8, u8, u8)) {
-        if x < WIDTH && y < HEIGHT {
-            let pos = (y * WIDTH + x) * 3;
-            self.pixels[pos] = rgb.0;
-            self.pixels[pos + 1] = rgb.1;
-            self.pixels[pos + 2] = rgb.2;
+impl Settings {
+    fn load(args: &Args) -> Result<Self, Box<dyn Error>> {
+        let mut settings: Settings = match args.value("config") {
+            Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+            None => Settings::default(),
+        };
+
+        if let Some(width) = args.get("width")? {
+            settings.width = width;
+        }
+        if let Some(height) = args.get("height")? {
+            settings.height = height;
+        }
+        if let Some(frames) = args.get("frames")? {
+            settings.frames = frames;
+        }
+        if let Some(zoom_speed) = args.get("zoom-speed")? {
+            settings.zoom_speed = zoom_speed;
+        }
+        if let Some(iterations) = args.get("iterations")? {
+            settings.iterations = iterations;
+        }
+        if let Some(rule) = args.get_with("rule", str::parse)? {
+            settings.rule = rule;
+        }
+        if let Some(maps) = args.get_with("maps", str::parse)? {
+            settings.maps = maps;
+        }
+        if let Some(target) = args.get_with("target", cli::parse_pair)? {
+            settings.target = target;
+        }
+        if let Some(fill) = args.get_with("fill", cli::parse_rgb)? {
+            settings.fill = fill;
+        }
+        if let Some(border) = args.get_with("border", cli::parse_rgb)? {
+            settings.border = border;
+        }
+        if let Some(background) = args.get_with("background", cli::parse_rgba)? {
+            settings.background = background;
+        }
+        if let Some(output) = args.value("output") {
+            settings.output = output.to_string();
+        }
+        if let Some(camera) = args.value("camera") {
+            settings.camera = Some(camera.to_string());
+        }
+        if let Some(fps) = args.get("fps")? {
+            settings.fps = fps;
+        }
+        if let Some(quality) = args.get("quality")? {
+            settings.quality = quality;
         }
+        if let Some(delay) = args.get_with("delay", timing::parse_delay)? {
+            settings.timing.delay_ms = delay;
+        }
+        if let Some(hold) = args.get("hold-first")? {
+            settings.timing.hold_first_ms = hold;
+        }
+        if let Some(hold) = args.get("hold-last")? {
+            settings.timing.hold_last_ms = hold;
+        }
+        if let Some(easing) = args.get("timing")? {
+            settings.timing.easing = easing;
+        }
+        if let Some(plays) = args.get("plays")? {
+            settings.timing.plays = plays;
+        }
+
+        settings.validate()?;
+        Ok(settings)
     }
 
-    fn clear(&mut self) {
-        self.pixels.fill(0);
+    fn validate(&self) -> Result<(), String> {
+        let max = u16::MAX as usize;
+        if self.width == 0 || self.width > max || self.height == 0 || self.height > max {
+            return Err(format!(
+                "dimensions invalides {}×{} (1 à {} pixels)",
+                self.width, self.height, max
+            ));
+        }
+        if self.fps == 0 {
+            return Err("fps doit être positif".to_string());
+        }
+        if self.zoom_speed <= 0.0 {
+            return Err(format!("zoom_speed doit être positif: {}", self.zoom_speed));
+        }
+        self.maps.validate()
     }
-}
 
-fn generate_cantor_squares(iterations: u32) -> Vec<Square> {
-    let mut squares = vec![Square {
-        x: 0.0,
-        y: 0.0,
-        side: 1.0,
-    }];
-
-    for _ in 0..iterations {
-        let mut new_squares = Vec::new();
-        for sq in &squares {
-            let new_side = sq.side / 3.0;
-            new_squares.push(Square {
-                x: sq.x,
-                y: sq.y,
-                side: new_side,
-            });
-            new_squares.push(Square {
-                x: sq.x + 2.0 * new_side,
-                y: sq.y,
-                side: new_side,
-            });
-            new_squares.push(Square {
-                x: sq.x,
-                y: sq.y + 2.0 * new_side,
-                side: new_side,
-            });
-            new_squares.push(Square {
-                x: sq.x + 2.0 * new_side,
-                y: sq.y + 2.0 * new_side,
-                side: new_side,
-            });
-        }
-        squares = new_squares;
+    fn camera_path(&self) -> Result<CameraPath, Box<dyn Error>> {
+        match &self.camera {
+            Some(path) => CameraPath::load(path),
+            // La vue de départ fait 1/3 du carré unité
+            None => Ok(CameraPath::exponential_zoom(
+                [self.target[0] as f64, self.target[1] as f64],
+                3.0,
+                self.zoom_speed as f64,
+                self.frames,
+            )),
+        }
     }
 
-    squares
-}
+    fn animation_options(&self) -> AnimationOptions {
+        AnimationOptions {
+            plays: self.timing.plays,
+            video: VideoOptions {
+                fps: self.fps,
+                quality: self.quality,
+            },
+            metadata: self.metadata(),
+        }
+    }
 
-fn render_frame_parallel(
-    cantor_squares: &[Square],
-    zoom: f32,
-    zoom_center_x: f32,
-    zoom_center_y: f32,
-    zoom_size: f32,
-) -> Vec<u8> {
-    let viewport_size = zoom_size / zoom;
-    let viewport_x = zoom_center_x - viewport_size / 2.0;
-    let viewport_y = zoom_center_y - viewport_size / 2.0;
-
-    // Créer un buffer par thread, puis les fusionner
-    let buffers: Vec<Vec<u8>> = (0..WIDTH)
-        .into_par_iter()
-        .map(|x| {
-            let mut line = vec![0u8; HEIGHT * 3];
-            
-            for y in 0..HEIGHT {
-                // Initialiser en noir
-                let pos = y * 3;
-                line[pos] = 0;
-                line[pos + 1] = 0;
-                line[pos + 2] = 0;
-            }
-
-            // Dessiner les carrés pour cette colonne
-            for sq in cantor_squares {
-                if sq.x + sq.side > viewport_x && sq.x < viewport_x + viewport_size &&
-                   sq.y + sq.side > viewport_y && sq.y < viewport_y + viewport_size {
-                    
-                    let screen_x = (sq.x - viewport_x) / viewport_size;
-                    let screen_y = (sq.y - viewport_y) / viewport_size;
-                    let screen_side = sq.side / viewport_size;
-
-                    let x1 = (screen_x * WIDTH as f32) as usize;
-                    let y1 = (screen_y * HEIGHT as f32) as usize;
-                    let x2 = ((screen_x + screen_side) * WIDTH as f32) as usize;
-                    let y2 = ((screen_y + screen_side) * HEIGHT as f32) as usize;
-
-                    // Dessiner la colonne de ce carré
-                    if x >= x1 && x < x2.min(WIDTH) {
-                        for y in y1..y2.min(HEIGHT) {
-                            let pos = y * 3;
-                            // Remplir
-                            if y >= y1 && y < y2 {
-                                line[pos] = 50;
-                                line[pos + 1] = 150;
-                                line[pos + 2] = 255;
-                            }
-                            // Bordures
-                            if y == y1 || y == y2 - 1 || x == x1 || x == x2 - 1 {
-                                line[pos] = 255;
-                                line[pos + 1] = 255;
-                                line[pos + 2] = 255;
-                            }
-                        }
-                    }
-                }
-            }
-
-            line
-        })
-        .collect();
-
-    // Fusionner les lignes
-    let mut pixels = vec![0u8; WIDTH * HEIGHT * 3];
-    for (x, line) in buffers.iter().enumerate() {
-        for y in 0..HEIGHT {
-            let src_pos = y * 3;
-            let dst_pos = (y * WIDTH + x) * 3;
-            pixels[dst_pos] = line[src_pos];
-            pixels[dst_pos + 1] = line[src_pos + 1];
-            pixels[dst_pos + 2] = line[src_pos + 2];
+    /// Parameters of the construction; the camera path is not included.
+    fn metadata(&self) -> metadata::Entries {
+        let [r, g, b] = self.fill;
+        let mut entries = metadata::generator();
+        entries.extend([
+            entry(metadata::RULE, &self.rule),
+            entry(metadata::ITERATIONS, self.iterations),
+            entry(metadata::PALETTE, format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        ]);
+        if !self.maps.is_empty() {
+            entries.push(entry(metadata::MAPS, &self.maps));
         }
+        entries
     }
 
-    pixels
+    fn style(&self) -> Style {
+        Style {
+            background: self.background,
+            fill: [self.fill[0], self.fill[1], self.fill[2], 255],
+            border: Some([self.border[0], self.border[1], self.border[2], 255]),
+            coloring: Coloring::Uniform,
+            palette: Vec::new(),
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    println!("Generating Cantor Square Zoom GIF (parallelized)...");
-    
-    let mut image = File::create("cantor_zoom.gif")?;
-    let mut encoder = Encoder::new(&mut image, WIDTH as u16, HEIGHT as u16, &[])?;
-    encoder.set_repeat(Repeat::Infinite)?;
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let settings = Settings::load(&args)?;
+    let path = settings.camera_path()?;
 
-    let cantor_squares = generate_cantor_squares(ITERATIONS);
-    
-    let zoom_center_x = 1.0 / 3.0;
-    let zoom_center_y = 1.0 / 3.0;
-    let zoom_size = 1.0 / 3.0;
+    println!("Generating Cantor Square Zoom animation (parallelized)...");
+    if let Some(notice) = video::fallback_notice(&settings.output) {
+        println!("{}", notice);
+    }
 
-    let mut zoom = 1.0f32;
+    let mut writer = animation::create(
+        &settings.output,
+        settings.width as u32,
+        settings.height as u32,
+        settings.frames,
+        &settings.animation_options(),
+    )?;
 
-    for frame_num in 0..NUM_FRAMES {
-        println!("Frame {}/{}", frame_num + 1, NUM_FRAMES);
-        
-        let pixels = render_frame_parallel(&cantor_squares, zoom, zoom_center_x, zoom_center_y, zoom_size);
+    let style = settings.style();
+    let delays = settings.timing.delays(settings.frames);
 
-        let frame = Frame::from_rgb(WIDTH as u16, HEIGHT as u16, &pixels);
-        encoder.write_frame(&frame)?;
+    // Les frames suivantes sont calculées pendant l'encodage de la courante
+    pipeline::stream(
+        settings.frames,
+        pipeline::default_lookahead(),
+        |frame_num| {
+            let camera = path.frame(frame_num, settings.frames);
+            render::render_rgba(
+                &camera,
+                settings.width,
+                settings.height,
+                &settings.rule,
+                None,
+                &settings.maps,
+                settings.iterations,
+                &style,
+            )
+        },
+        |frame_num, image| {
+            println!("Frame {}/{}", frame_num + 1, settings.frames);
+            writer.write_frame(&image, delays[frame_num as usize])
+        },
+    )?;
 
-        zoom *= ZOOM_SPEED;
-    }
+    writer.finish()?;
 
-    println!("✨ GIF saved as cantor_zoom.gif!");
+    println!("✨ Animation saved as {}!", settings.output);
     Ok(())
-}
\ No newline at end of file
+}
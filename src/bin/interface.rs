@@ -1,11 +1,23 @@
 use eframe::egui;
 use image::{Rgba, RgbaImage};
-use svg::node::element::Rectangle;
-use rand::Rng;
-use svg::Document;
-use gif::{Encoder, Frame, Repeat};
-use std::fs::File;
-use rayon::prelude::*;
+use fractal_generation_rust::camera::{Camera, CameraPath, Easing, Keyframe};
+use fractal_generation_rust::affine::{Map, Maps};
+use fractal_generation_rust::dust::Selection;
+use fractal_generation_rust::format::{ImageFormat, PngCompression};
+use fractal_generation_rust::history::History;
+use fractal_generation_rust::ifs::{self, Ifs, Mode};
+use fractal_generation_rust::interval::{Figure, Interval};
+use fractal_generation_rust::metadata;
+use fractal_generation_rust::preset::{self, Preset};
+use fractal_generation_rust::project::Project;
+use fractal_generation_rust::randomize::{self, Locks, Seed};
+use fractal_generation_rust::render::{self, Coloring};
+use fractal_generation_rust::rule::Rule;
+use fractal_generation_rust::stats::{self, Measures, PixelStats};
+use fractal_generation_rust::video;
+
+// Présets de l'utilisateur, rechargés au démarrage
+const PRESETS_FILE: &str = "presets.toml";
 
 fn main() -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions {
@@ -18,7 +30,18 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             customize_style(&cc.egui_ctx);
-            Ok(Box::new(FraCantor::default()))
+            // Un fichier projet peut être passé en argument
+            let mut app = FraCantor::default();
+            if std::path::Path::new(PRESETS_FILE).exists() {
+                match preset::load(PRESETS_FILE) {
+                    Ok(presets) => app.user_presets = presets,
+                    Err(err) => eprintln!("Erreur chargement {}: {}", PRESETS_FILE, err),
+                }
+            }
+            if let Some(path) = std::env::args().nth(1) {
+                app.open_project(&path);
+            }
+            Ok(Box::new(app))
         }),
     )
 }
@@ -77,34 +100,104 @@ fn customize_style(ctx: &egui::Context) {
     ctx.set_style(style);
 }
 
-struct FraCantor {
+/// Fenêtre IFS : système courant, réglages du rendu et dernière image.
+struct IfsWindow {
+    open: bool,
+    system: Ifs,
+    mode: Mode,
+    points: u64,
     iterations: u32,
-    zoom: f32,
-    selected_color: usize,
+    gamma: f64,
+    path: String,
+    image: Option<RgbaImage>,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Default for IfsWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            system: ifs::named("fougere").expect("système de la galerie"),
+            mode: Mode::Chaos,
+            points: 1_000_000,
+            iterations: 10,
+            gamma: 2.2,
+            path: "systeme.toml".to_string(),
+            image: None,
+            texture: None,
+        }
+    }
+}
+
+/// Fenêtre de saisie d'un chemin de projet
+#[derive(Clone, Copy, PartialEq)]
+enum ProjectDialog {
+    Open,
+    SaveAs,
+}
+
+struct FraCantor {
+    project: Project,
+    /// États précédents du projet pour annuler / rétablir
+    history: History<Project>,
+    show_history: bool,
+    /// Fichier du projet courant, s'il a déjà été ouvert ou enregistré
+    project_path: Option<String>,
+    project_dialog: Option<ProjectDialog>,
+    dialog_path: String,
+    status: String,
     texture: Option<egui::TextureHandle>,
-    colors: Vec<Rgba<u8>>,
+    gallery: Vec<Preset>,
+    user_presets: Vec<Preset>,
+    show_presets: bool,
+    preset_name: String,
+    presets_path: String,
+    show_rule_editor: bool,
+    /// Graine saisie pour rejouer un tirage « Mystère »
+    seed: String,
+    locks: Locks,
+    show_mystery: bool,
+    show_camera_editor: bool,
+    camera_preview: Option<f64>,
+    camera_path: String,
+    pixel_stats: Option<PixelStats>,
+    ifs: IfsWindow,
 }
 
 impl Default for FraCantor {
     fn default() -> Self {
         Self {
-            iterations: 1,
-            zoom: 1.0,
-            selected_color: 0,
+            project: Project::default(),
+            history: History::new(Project::default(), 100),
+            show_history: false,
+            project_path: None,
+            project_dialog: None,
+            dialog_path: "projet.toml".to_string(),
+            status: String::new(),
             texture: None,
-            colors: vec![
-                Rgba([239, 71, 111, 255]),   // rose/rouge flashy
-                Rgba([255, 209, 102, 255]),  // jaune pastel
-                Rgba([6, 214, 160, 255]),    // vert menthe
-                Rgba([17, 138, 178, 255]),   // bleu vif
-                Rgba([155, 111, 242, 255]),  // violet
-            ],
+            gallery: preset::gallery(),
+            user_presets: Vec::new(),
+            show_presets: false,
+            preset_name: String::new(),
+            presets_path: "mes_presets.toml".to_string(),
+            show_rule_editor: false,
+            seed: String::new(),
+            locks: Locks::default(),
+            show_mystery: false,
+            show_camera_editor: false,
+            camera_preview: None,
+            camera_path: "camera.toml".to_string(),
+            pixel_stats: None,
+            ifs: IfsWindow::default(),
         }
     }
 }
 
 impl eframe::App for FraCantor {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_history_shortcuts(ctx);
+        self.render_menu_bar(ctx);
+
         egui::CentralPanel::default()
             .frame(egui::Frame::none().fill(egui::Color32::from_rgb(249, 250, 251)))
             .show(ctx, |ui| {
@@ -205,6 +298,15 @@ impl eframe::App for FraCantor {
                     });
                 });
             });
+
+        self.render_camera_editor(ctx);
+        self.render_rule_editor(ctx);
+        self.render_ifs_window(ctx);
+        self.render_mystery_window(ctx);
+        self.render_presets_window(ctx);
+        self.render_project_dialog(ctx);
+        self.render_history_window(ctx);
+        self.record_history(ctx);
     }
 }
 
@@ -234,6 +336,14 @@ impl FraCantor {
                             .color(egui::Color32::from_rgb(40, 40, 40))
                             .strong(),
                     );
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("Règle…").clicked() {
+                            self.show_rule_editor = !self.show_rule_editor;
+                        }
+                        if ui.small_button("IFS…").clicked() {
+                            self.ifs.open = !self.ifs.open;
+                        }
+                    });
                 });
 
                 ui.add_space(8.0);
@@ -241,22 +351,22 @@ impl FraCantor {
                 ui.horizontal(|ui| {
                     ui.label("Itérations");
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(self.iterations.to_string());
+                        ui.label(self.project.iterations.to_string());
                     });
                 });
 
-                ui.add(egui::Slider::new(&mut self.iterations, 0..=6).show_value(false));
+                ui.add(egui::Slider::new(&mut self.project.iterations, 0..=6).show_value(false));
 
                 ui.add_space(6.0);
 
                 ui.horizontal(|ui| {
                     ui.label("Zoom");
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(format!("x{}", self.zoom as i32));
+                        ui.label(format!("x{}", self.project.zoom as i32));
                     });
                 });
 
-                ui.add(egui::Slider::new(&mut self.zoom, 1.0..=15.0).show_value(false));
+                ui.add(egui::Slider::new(&mut self.project.zoom, 1.0..=15.0).show_value(false));
             });
     }
 
@@ -285,37 +395,61 @@ impl FraCantor {
                             .color(egui::Color32::from_rgb(40, 40, 40))
                             .strong(),
                     );
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("Graine…").clicked() {
+                            self.show_mystery = !self.show_mystery;
+                        }
+                    });
                 });
 
                 ui.add_space(8.0);
 
-                ui.vertical_centered(|ui| {
-                    egui::Grid::new("presets_grid")
-                        .spacing([10.0, 8.0])
-                        .show(ui, |ui| {
-                            let button_size = egui::vec2(120.0, 28.0);
+                let names: Vec<String> = self
+                    .gallery
+                    .iter()
+                    .chain(&self.user_presets)
+                    .map(|preset| preset.name.clone())
+                    .collect();
+                let mut chosen = None;
 
-                            if ui.add_sized(button_size, egui::Button::new("1 itération")).clicked() {
-                                self.iterations = 1;
+                ui.vertical_centered(|ui| {
+                    egui::ComboBox::from_id_salt("presets_combo")
+                        .width(250.0)
+                        .selected_text("Appliquer un préset…")
+                        .show_ui(ui, |ui| {
+                            for (i, name) in names.iter().enumerate() {
+                                if i == self.gallery.len() {
+                                    ui.separator();
+                                }
+                                if ui.selectable_label(false, name).clicked() {
+                                    chosen = Some(i);
+                                }
                             }
+                        });
 
-                            if ui.add_sized(button_size, egui::Button::new("2 itérations")).clicked() {
-                                self.iterations = 2;
-                            }
+                    ui.add_space(6.0);
 
-                            ui.end_row();
+                    ui.horizontal(|ui| {
+                        let button_size = egui::vec2(80.0, 24.0);
 
-                            if ui.add_sized(button_size, egui::Button::new("5 itérations")).clicked() {
-                                self.iterations = 5;
-                            }
+                        if ui.add_sized(button_size, egui::Button::new("Mystère")).clicked() {
+                            self.roll(randomize::new_seed());
+                        }
 
-                            if ui.add_sized(button_size, egui::Button::new("Mystère")).clicked() {
-                                let mut rng = rand::rng();
-                                self.iterations = rng.random_range(0..=6);
-                                self.zoom = 1.0;
-                            }
-                        });
+                        if ui.add_sized(button_size, egui::Button::new("Enregistrer")).clicked() {
+                            let name = format!("Préset {}", self.user_presets.len() + 1);
+                            self.add_user_preset(name);
+                        }
+
+                        if ui.add_sized(button_size, egui::Button::new("Gérer…")).clicked() {
+                            self.show_presets = !self.show_presets;
+                        }
+                    });
                 });
+
+                if let Some(i) = chosen {
+                    self.apply_preset(i);
+                }
             });
     }
 
@@ -344,12 +478,22 @@ impl FraCantor {
                             .color(egui::Color32::from_rgb(40, 40, 40))
                             .strong(),
                     );
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        egui::ComboBox::from_id_salt("coloring_combo")
+                            .width(90.0)
+                            .selected_text(self.project.coloring.label())
+                            .show_ui(ui, |ui| {
+                                for coloring in Coloring::ALL {
+                                    ui.selectable_value(&mut self.project.coloring, coloring, coloring.label());
+                                }
+                            });
+                    });
                 });
 
                 ui.add_space(12.0);
 
                 ui.horizontal(|ui| {
-                    for (i, color) in self.colors.iter().enumerate() {
+                    for (i, color) in self.project.palette.iter().enumerate() {
                         let size = egui::vec2(45.0, 45.0);
                         let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
 
@@ -359,7 +503,7 @@ impl FraCantor {
 
                         ui.painter().rect_filled(rect, 6.0, color32);
 
-                        if i == self.selected_color {
+                        if i == self.project.selected_color {
                             ui.painter().rect_stroke(
                                 rect,
                                 6.0,
@@ -368,7 +512,7 @@ impl FraCantor {
                         }
 
                         if response.clicked() {
-                            self.selected_color = i;
+                            self.project.selected_color = i;
                         }
 
                         ui.add_space(4.0);
@@ -402,6 +546,11 @@ impl FraCantor {
                             .color(egui::Color32::from_rgb(40, 40, 40))
                             .strong(),
                     );
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("Caméra…").clicked() {
+                            self.show_camera_editor = !self.show_camera_editor;
+                        }
+                    });
                 });
 
                 ui.add_space(8.0);
@@ -416,9 +565,12 @@ impl FraCantor {
                                 self.export_image("cantor.png");
                             }
 
-                            if ui.add_sized(button_size, egui::Button::new("JPEG")).clicked() {
-                                self.export_image("cantor.jpg");
-                            }
+                            ui.allocate_ui(button_size, |ui| {
+                                ui.set_min_size(button_size);
+                                ui.centered_and_justified(|ui| {
+                                    ui.menu_button("Image", |ui| self.render_image_menu(ui));
+                                });
+                            });
 
                             ui.end_row();
 
@@ -426,14 +578,49 @@ impl FraCantor {
                                 self.export_svg("cantor.svg");
                             }
 
-                            if ui.add_sized(button_size, egui::Button::new("GIF")).clicked() {
-                                self.export_gif("cantor.gif");
-                            }
+                            ui.allocate_ui(button_size, |ui| {
+                                ui.set_min_size(button_size);
+                                ui.centered_and_justified(|ui| {
+                                    ui.menu_button("Animation", |ui| {
+                                        // .apng : le bouton PNG garde cantor.png pour l'image fixe
+                                        for filename in ["cantor.gif", "cantor.apng", "cantor.webp", "cantor.mp4", "cantor.webm"] {
+                                            let format = filename.trim_start_matches("cantor.").to_uppercase();
+                                            if ui.button(format).clicked() {
+                                                self.export_animation(filename);
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    });
+                                });
+                            });
                         });
                 });
             });
     }
 
+    /// Formats of the still export and their options.
+    fn render_image_menu(&mut self, ui: &mut egui::Ui) {
+        for format in ImageFormat::ALL {
+            if ui.button(format.label()).clicked() {
+                self.export_image(&format!("cantor.{}", format.extension()));
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+
+        let options = &mut self.project.export.image;
+        ui.label("Qualité JPEG");
+        ui.add(egui::Slider::new(&mut options.jpeg_quality, 1..=100));
+        ui.label("Compression PNG");
+        ui.horizontal(|ui| {
+            for compression in PngCompression::ALL {
+                ui.radio_value(&mut options.png_compression, compression, compression.label());
+            }
+        });
+        ui.checkbox(&mut options.sixteen_bit, "16 bits (PNG, TIFF)");
+        ui.checkbox(&mut options.alpha, "Canal alpha");
+    }
+
     fn render_stats_section(&mut self, ui: &mut egui::Ui) {
         let available = ui.available_size();
         egui::Frame::none()
@@ -463,16 +650,72 @@ impl FraCantor {
 
                 ui.add_space(12.0);
 
-                let num_squares = 4_u32.pow(self.iterations);
+                let maps = &self.project.maps;
+                let (measures, mut squares) = if maps.is_empty() {
+                    (
+                        Measures::new(&self.project.rule, self.project.iterations),
+                        stats::power_label(self.project.rule.kept(), self.project.iterations),
+                    )
+                } else {
+                    // Multi-échelle : dimension de similitude par l'équation de Moran
+                    (
+                        Measures::from_maps(maps, self.project.iterations),
+                        stats::power_label(maps.0.len() as u32, self.project.iterations),
+                    )
+                };
+                let (mut dimension, mut area, mut perimeter) = (
+                    format!("{:.4}", measures.dimension),
+                    format!("{:.2} %", measures.area * 100.0),
+                    format!("{:.2}", measures.perimeter),
+                );
+                // Poussière aléatoire : valeurs attendues
+                if let Some(dust) = self.project.dust().filter(|_| maps.is_empty()) {
+                    let iterations = self.project.iterations;
+                    squares = format!("≈ {:.3e}", dust.expected_squares(iterations));
+                    dimension = format!("≈ {:.4}", dust.expected_dimension());
+                    let cells = (dust.grid * dust.grid) as f64;
+                    area = format!("≈ {:.2} %", (dust.expected_kept() / cells).powf(iterations as f64) * 100.0);
+                    perimeter = "-".to_string();
+                }
+                // Figures 1D : segments de la première ligne de la règle
+                let one_dimensional = self.project.figure != Figure::Square;
+                if one_dimensional {
+                    let interval = Interval::from_rule(&self.project.rule);
+                    let (grid, kept, iterations) = (interval.grid as f64, interval.kept(), self.project.iterations);
+                    squares = stats::power_label(kept, iterations);
+                    dimension = format!("{:.4}", interval.dimension());
+                    area = format!("{:.2} %", (kept as f64 / grid).powf(iterations as f64) * 100.0);
+                    perimeter = "-".to_string();
+                }
+                let filled = match self.pixel_stats {
+                    Some(pixels) => format!("{:.2} %", pixels.fraction() * 100.0),
+                    None => "-".to_string(),
+                };
 
-                ui.horizontal(|ui| {
+                let stat = |ui: &mut egui::Ui, name: &str, value: String| {
                     ui.label(
-                        egui::RichText::new("Carrés :")
+                        egui::RichText::new(name)
+                            .size(12.0)
                             .color(egui::Color32::from_rgb(189, 0, 0))
                             .strong(),
                     );
-                    ui.label(num_squares.to_string());
-                });
+                    ui.label(egui::RichText::new(value).size(12.0));
+                };
+
+                egui::Grid::new("stats_grid")
+                    .num_columns(4)
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        stat(ui, if one_dimensional { "Segments :" } else { "Carrés :" }, squares);
+                        stat(ui, "Dimension :", dimension);
+                        ui.end_row();
+                        stat(ui, if one_dimensional { "Longueur :" } else { "Aire :" }, area);
+                        stat(ui, "Périmètre :", perimeter);
+                        ui.end_row();
+                        stat(ui, "Côté :", format!("{:.2e}", measures.side));
+                        stat(ui, "Pixels :", filled);
+                        ui.end_row();
+                    });
             });
     }
 
@@ -480,8 +723,20 @@ impl FraCantor {
         let available_size = ui.available_size();
         let size = available_size.min_elem() as u32;
 
-        let image = self.generate_cantor_image(size);
-        let color_image = self.rgba_to_color_image(&image);
+        // Aperçu du chemin de caméra à l'instant choisi dans l'éditeur
+        let color_image = match self.camera_preview {
+            Some(time) => {
+                let camera = self.project.camera.sample(time);
+                let side = size as usize;
+                let image = render::render_rgba(&camera, side, side, &self.project.rule, self.project.dust().as_ref(), &self.project.maps, self.project.iterations, &self.project.style());
+                self.rgba_to_color_image(&image)
+            }
+            None => {
+                let image = self.project.render_image(size);
+                self.pixel_stats = Some(PixelStats::count(&image, Rgba([255, 255, 255, 255])));
+                self.rgba_to_color_image(&image)
+            }
+        };
 
         let texture = self.texture.get_or_insert_with(|| {
             ctx.load_texture("cantor", color_image.clone(), egui::TextureOptions::default())
@@ -492,232 +747,898 @@ impl FraCantor {
         ui.image(&*texture);
     }
 
-    fn generate_cantor_image(&self, size: u32) -> RgbaImage {
-        let mut image = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
-        let color = self.colors[self.selected_color];
+    fn rgba_to_color_image(&self, img: &RgbaImage) -> egui::ColorImage {
+        let size = [img.width() as usize, img.height() as usize];
+        let pixels = img
+            .pixels()
+            .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+            .collect();
+        egui::ColorImage { size, pixels }
+    }
+
+    fn export_image(&mut self, filename: &str) {
+        self.status = match self.project.export_image(filename, self.project.export.image_size) {
+            Ok(()) => format!("✓ Image sauvegardée: {}", filename),
+            Err(err) => format!("Erreur sauvegarde {}: {}", filename, err),
+        };
+        println!("{}", self.status);
+    }
+
+    fn export_svg(&mut self, filename: &str) {
+        let document = self.project.render_svg(self.project.export.image_size);
+        self.status = match svg::save(filename, &document) {
+            Ok(()) => format!("✓ SVG sauvegardé: {}", filename),
+            Err(err) => format!("Erreur sauvegarde {}: {}", filename, err),
+        };
+        println!("{}", self.status);
+    }
+
+    fn export_animation(&mut self, filename: &str) {
+        println!("Génération de l'animation...");
+        let notice = video::fallback_notice(filename);
+        let progress = |frame, frames| println!("Frame {}/{}", frame, frames);
+        self.status = match self.project.export_animation(filename, progress) {
+            Ok(()) => match notice {
+                Some(_) => format!("✓ Frames PNG sauvegardées: {} (ffmpeg introuvable)", video::fallback_directory(filename).display()),
+                None => format!("✓ Animation sauvegardée: {}", filename),
+            },
+            Err(err) => format!("Erreur export {}: {}", filename, err),
+        };
+        println!("{}", self.status);
+    }
+
+    fn render_menu_bar(&mut self, ctx: &egui::Context) {
+        // Raccourcis : Ctrl+O, Ctrl+S, Ctrl+Shift+S
+        let shortcut = |modifiers, key| egui::KeyboardShortcut::new(modifiers, key);
+        let (open, save_as, save) = ctx.input_mut(|input| {
+            (
+                input.consume_shortcut(&shortcut(egui::Modifiers::COMMAND, egui::Key::O)),
+                input.consume_shortcut(&shortcut(
+                    egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                    egui::Key::S,
+                )),
+                input.consume_shortcut(&shortcut(egui::Modifiers::COMMAND, egui::Key::S)),
+            )
+        });
+        if open {
+            self.project_dialog = Some(ProjectDialog::Open);
+        }
+        if save_as {
+            self.project_dialog = Some(ProjectDialog::SaveAs);
+        } else if save {
+            self.save_current_project();
+        }
 
-        let view_size = size as f32 / self.zoom;
-        let view_start_x = 0.0;
-        let view_start_y = 0.0;
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("Projet", |ui| {
+                    if ui.button("Nouveau").clicked() {
+                        self.project = Project::default();
+                        self.project_path = None;
+                        self.status.clear();
+                        self.history.record(&self.project, "Nouveau projet");
+                        ui.close_menu();
+                    }
+                    if ui.button("Ouvrir…").clicked() {
+                        self.project_dialog = Some(ProjectDialog::Open);
+                        ui.close_menu();
+                    }
+                    if ui.button("Enregistrer").clicked() {
+                        self.save_current_project();
+                        ui.close_menu();
+                    }
+                    if ui.button("Enregistrer sous…").clicked() {
+                        self.project_dialog = Some(ProjectDialog::SaveAs);
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Édition", |ui| {
+                    if ui
+                        .add_enabled(self.history.can_undo(), egui::Button::new("Annuler").shortcut_text("Ctrl+Z"))
+                        .clicked()
+                    {
+                        self.undo();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.history.can_redo(), egui::Button::new("Rétablir").shortcut_text("Ctrl+Shift+Z"))
+                        .clicked()
+                    {
+                        self.redo();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Historique…").clicked() {
+                        self.show_history = !self.show_history;
+                        ui.close_menu();
+                    }
+                });
 
-        let mut full_image = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
-        self.draw_cantor_square(&mut full_image, 0, 0, size, self.iterations, color);
+                ui.label(
+                    egui::RichText::new(self.project_path.as_deref().unwrap_or("Sans titre"))
+                        .color(egui::Color32::from_rgb(120, 120, 120)),
+                );
 
-        for py in 0..size {
-            for px in 0..size {
-                let src_x = view_start_x + (px as f32 * view_size / size as f32);
-                let src_y = view_start_y + (py as f32 * view_size / size as f32);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(&self.status);
+                });
+            });
+        });
+    }
 
-                if src_x >= 0.0 && src_x < size as f32 && src_y >= 0.0 && src_y < size as f32 {
-                    let pixel = full_image.get_pixel(src_x as u32, src_y as u32);
-                    image.put_pixel(px, py, *pixel);
+    fn render_project_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.project_dialog else {
+            return;
+        };
+        let (title, action) = match dialog {
+            ProjectDialog::Open => ("Ouvrir un projet", "Ouvrir"),
+            ProjectDialog::SaveAs => ("Enregistrer le projet sous", "Enregistrer"),
+        };
+
+        let mut open = true;
+        let mut confirmed = false;
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Fichier");
+                    let response = ui.text_edit_singleline(&mut self.dialog_path);
+                    confirmed = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+                ui.add_space(8.0);
+                if ui.button(action).clicked() {
+                    confirmed = true;
                 }
+            });
+
+        if confirmed {
+            let path = self.dialog_path.clone();
+            match dialog {
+                ProjectDialog::Open => self.open_project(&path),
+                ProjectDialog::SaveAs => self.save_project(&path),
             }
+            open = false;
         }
+        if !open {
+            self.project_dialog = None;
+        }
+    }
 
-        image
+    fn open_project(&mut self, path: &str) {
+        match Project::load(path) {
+            Ok(project) => {
+                self.project = project;
+                self.seed = self.project.seed.to_string();
+                self.project_path = Some(path.to_string());
+                self.status = format!("✓ Projet ouvert: {}", path);
+                self.history.record(&self.project, format!("Ouverture de {}", path));
+            }
+            Err(err) => self.status = format!("Erreur ouverture {}: {}", path, err),
+        }
+        println!("{}", self.status);
+    }
+
+    fn save_project(&mut self, path: &str) {
+        match self.project.save(path) {
+            Ok(()) => {
+                self.project_path = Some(path.to_string());
+                self.status = format!("✓ Projet sauvegardé: {}", path);
+            }
+            Err(err) => self.status = format!("Erreur sauvegarde {}: {}", path, err),
+        }
+        println!("{}", self.status);
     }
 
-    fn draw_cantor_square(
-        &self,
-        image: &mut RgbaImage,
-        x: i32,
-        y: i32,
-        size: u32,
-        iterations: u32,
-        color: Rgba<u8>,
-    ) {
-        if size == 0 {
+    /// « Enregistrer » : demande un fichier la première fois
+    fn save_current_project(&mut self) {
+        match self.project_path.clone() {
+            Some(path) => self.save_project(&path),
+            None => self.project_dialog = Some(ProjectDialog::SaveAs),
+        }
+    }
+
+    fn handle_history_shortcuts(&mut self, ctx: &egui::Context) {
+        // Un champ texte actif garde ses propres Ctrl+Z
+        if ctx.wants_keyboard_input() {
             return;
         }
+        let (redo, undo) = ctx.input_mut(|input| {
+            let redo_shift = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z);
+            let redo_y = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+            let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+            (
+                input.consume_shortcut(&redo_shift) || input.consume_shortcut(&redo_y),
+                input.consume_shortcut(&undo),
+            )
+        });
+        if redo {
+            self.redo();
+        } else if undo {
+            self.undo();
+        }
+    }
 
-        if iterations == 0 {
-            self.draw_filled_rectangle(image, x, y, size, color);
+    /// Enregistre les modifications de la frame, une fois le geste terminé :
+    /// un glissement de slider ne compte que pour une étape.
+    fn record_history(&mut self, ctx: &egui::Context) {
+        if ctx.input(|input| input.pointer.any_down()) || ctx.wants_keyboard_input() {
             return;
         }
+        let label = describe_change(self.history.current(), &self.project);
+        self.history.record(&self.project, label);
+    }
 
-        let sub_size = size / 3;
-
-        for i in [0, 2].iter() {
-            for j in [0, 2].iter() {
-                self.draw_cantor_square(
-                    image,
-                    x + (i * sub_size) as i32,
-                    y + (j * sub_size) as i32,
-                    sub_size,
-                    iterations - 1,
-                    color,
-                );
-            }
+    fn undo(&mut self) {
+        if let Some(project) = self.history.undo() {
+            self.project = project.clone();
         }
     }
 
-    fn draw_filled_rectangle(
-        &self,
-        image: &mut RgbaImage,
-        x: i32,
-        y: i32,
-        size: u32,
-        color: Rgba<u8>,
-    ) {
-        for i in 0..size {
-            for j in 0..size {
-                let px = x + i as i32;
-                let py = y + j as i32;
-                if px >= 0 && px < image.width() as i32 && py >= 0 && py < image.height() as i32 {
-                    image.put_pixel(px as u32, py as u32, color);
-                }
-            }
+    fn redo(&mut self) {
+        if let Some(project) = self.history.redo() {
+            self.project = project.clone();
         }
     }
 
-    fn rgba_to_color_image(&self, img: &RgbaImage) -> egui::ColorImage {
-        let size = [img.width() as usize, img.height() as usize];
-        let pixels = img
-            .pixels()
-            .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-            .collect();
-        egui::ColorImage { size, pixels }
+    fn render_history_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_history;
+        let (mut undo_steps, mut redo_steps) = (0, 0);
+
+        egui::Window::new("Historique")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(240.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    let done: Vec<&str> = self.history.done().collect();
+                    // Cliquer une étape revient à l'état juste après elle
+                    for (i, label) in done.iter().enumerate() {
+                        if ui.selectable_label(false, *label).clicked() {
+                            undo_steps = done.len() - i - 1;
+                        }
+                    }
+                    ui.label(
+                        egui::RichText::new("● État actuel")
+                            .color(egui::Color32::from_rgb(189, 0, 0))
+                            .strong(),
+                    );
+                    for (i, label) in self.history.undone().enumerate() {
+                        let text = egui::RichText::new(label).color(egui::Color32::from_rgb(160, 160, 160));
+                        if ui.selectable_label(false, text).clicked() {
+                            redo_steps = i + 1;
+                        }
+                    }
+                });
+            });
+
+        for _ in 0..undo_steps {
+            self.undo();
+        }
+        for _ in 0..redo_steps {
+            self.redo();
+        }
+        self.show_history = open;
+    }
+
+    /// Applique le préset `index` de la galerie suivie des présets utilisateur.
+    fn apply_preset(&mut self, index: usize) {
+        let Some(preset) = self.gallery.iter().chain(&self.user_presets).nth(index).cloned() else {
+            return;
+        };
+        preset.apply(&mut self.project);
+        self.history.record(&self.project, format!("Préset « {} »", preset.name));
     }
 
-    fn export_image(&self, filename: &str) {
-        let size = 2187;
-        let image = self.generate_cantor_image(size);
-        image.save(filename).expect("Erreur lors de la sauvegarde");
-        println!("✓ Image sauvegardée: {}", filename);
-    }
-
-    fn export_svg(&self, filename: &str) {
-        
-        let size = 2187; // 3^7 pour être divisible
-        let color = self.colors[self.selected_color];
-        
-        let mut document = Document::new()
-            .set("width", size)
-            .set("height", size)
-            .set("viewBox", (0, 0, size, size));
-        
-        // Dessiner le fond blanc
-        let background = Rectangle::new()
-            .set("x", 0)
-            .set("y", 0)
-            .set("width", size)
-            .set("height", size)
-            .set("fill", "white");
-        document = document.add(background);
-        
-        // Générer les rectangles de la fractale
-        let rectangles = self.generate_svg_rectangles(0, 0, size, self.iterations, color);
-        for rect in rectangles {
-            document = document.add(rect);
+    fn add_user_preset(&mut self, name: String) {
+        self.user_presets.push(Preset::from_project(name, &self.project));
+        self.save_user_presets();
+    }
+
+    fn save_user_presets(&mut self) {
+        if let Err(err) = preset::save(PRESETS_FILE, &self.user_presets) {
+            self.status = format!("Erreur sauvegarde {}: {}", PRESETS_FILE, err);
+            eprintln!("{}", self.status);
         }
-        
-        svg::save(filename, &document).expect("Erreur lors de la sauvegarde SVG");
-        println!("✓ SVG sauvegardé: {}", filename);
-    }
-
-    fn generate_svg_rectangles(
-        &self,
-        x: u32,
-        y: u32,
-        size: u32,
-        iterations: u32,
-        color: image::Rgba<u8>,
-    ) -> Vec<svg::node::element::Rectangle> {
-        use svg::node::element::Rectangle;
-        
-        let mut rectangles = Vec::new();
-        
-        if size == 0 {
-            return rectangles;
+    }
+
+    fn render_presets_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_presets;
+        let mut apply = None;
+        let mut remove = None;
+        let mut overwrite = None;
+        let mut changed = false;
+
+        egui::Window::new("Présets")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Galerie").strong());
+                egui::Grid::new("gallery_grid").spacing([8.0, 4.0]).show(ui, |ui| {
+                    for (i, preset) in self.gallery.iter().enumerate() {
+                        ui.label(&preset.name);
+                        ui.label(
+                            egui::RichText::new(preset.rule.to_string())
+                                .size(11.0)
+                                .color(egui::Color32::from_rgb(120, 120, 120)),
+                        );
+                        if ui.small_button("Appliquer").clicked() {
+                            apply = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.label(egui::RichText::new("Mes présets").strong());
+                if self.user_presets.is_empty() {
+                    ui.label("Aucun préset enregistré.");
+                }
+                egui::Grid::new("user_presets_grid").spacing([8.0, 4.0]).show(ui, |ui| {
+                    for (i, preset) in self.user_presets.iter_mut().enumerate() {
+                        changed |= ui
+                            .add(egui::TextEdit::singleline(&mut preset.name).desired_width(150.0))
+                            .lost_focus();
+                        if ui.small_button("Appliquer").clicked() {
+                            apply = Some(self.gallery.len() + i);
+                        }
+                        if ui.small_button("Écraser").on_hover_text("Remplacer par les réglages actuels").clicked() {
+                            overwrite = Some(i);
+                        }
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.preset_name).hint_text("Nom").desired_width(150.0));
+                    if ui.button("Enregistrer l'actuel").clicked() {
+                        let name = match self.preset_name.trim() {
+                            "" => format!("Préset {}", self.user_presets.len() + 1),
+                            name => name.to_string(),
+                        };
+                        self.user_presets.push(Preset::from_project(name, &self.project));
+                        self.preset_name.clear();
+                        changed = true;
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Fichier");
+                    ui.add(egui::TextEdit::singleline(&mut self.presets_path).desired_width(150.0));
+                    if ui.button("Exporter").clicked() {
+                        self.status = match preset::save(&self.presets_path, &self.user_presets) {
+                            Ok(()) => format!("✓ Présets exportés: {}", self.presets_path),
+                            Err(err) => format!("Erreur export {}: {}", self.presets_path, err),
+                        };
+                        println!("{}", self.status);
+                    }
+                    if ui.button("Importer").clicked() {
+                        match preset::load(&self.presets_path) {
+                            Ok(presets) => {
+                                self.status = format!("✓ {} présets importés: {}", presets.len(), self.presets_path);
+                                self.user_presets.extend(presets);
+                                changed = true;
+                            }
+                            Err(err) => self.status = format!("Erreur import {}: {}", self.presets_path, err),
+                        }
+                        println!("{}", self.status);
+                    }
+                });
+            });
+
+        if let Some(i) = overwrite {
+            let name = self.user_presets[i].name.clone();
+            self.user_presets[i] = Preset::from_project(name, &self.project);
+            changed = true;
         }
-        
-        if iterations == 0 {
-            let rect = Rectangle::new()
-                .set("x", x)
-                .set("y", y)
-                .set("width", size)
-                .set("height", size)
-                .set("fill", format!("rgb({},{},{})", color[0], color[1], color[2]));
-            rectangles.push(rect);
-            return rectangles;
+        if let Some(i) = remove {
+            self.user_presets.remove(i);
+            changed = true;
         }
-        
-        let sub_size = size / 3;
-        for i in [0, 2].iter() {
-            for j in [0, 2].iter() {
-                let mut sub_rects = self.generate_svg_rectangles(
-                    x + (i * sub_size),
-                    y + (j * sub_size),
-                    sub_size,
-                    iterations - 1,
-                    color,
+        if changed {
+            self.save_user_presets();
+        }
+        if let Some(i) = apply {
+            self.apply_preset(i);
+        }
+        self.show_presets = open;
+    }
+
+    /// Tire une variation du projet pour `seed`, sans toucher aux paramètres verrouillés.
+    fn roll(&mut self, seed: Seed) {
+        self.project = randomize::randomize(&self.project, seed, &self.locks);
+        self.seed = seed.to_string();
+        self.history.record(&self.project, format!("Mystère (graine {})", seed));
+    }
+
+    fn render_mystery_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_mystery;
+        let mut seed = None;
+
+        egui::Window::new("Mystère")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("Graine du projet : {}", self.project.seed))
+                        .color(egui::Color32::from_rgb(120, 120, 120)),
+                )
+                .on_hover_text("Enregistrée dans les images, SVG et GIF exportés");
+                ui.horizontal(|ui| {
+                    ui.label("Graine");
+                    ui.add(egui::TextEdit::singleline(&mut self.seed).hint_text("aucune").desired_width(180.0));
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Nouvelle graine").clicked() {
+                        seed = Some(randomize::new_seed());
+                    }
+                    if ui.button("Rejouer").on_hover_text("Refaire le tirage de cette graine").clicked() {
+                        match self.seed.trim().parse() {
+                            Ok(value) => seed = Some(value),
+                            Err(_) => self.status = format!("Graine invalide: {}", self.seed),
+                        }
+                    }
+                });
+
+                ui.add_space(6.0);
+                ui.label(egui::RichText::new("Garder").strong());
+                ui.checkbox(&mut self.locks.rule, "Règle");
+                ui.checkbox(&mut self.locks.iterations, "Itérations");
+                ui.checkbox(&mut self.locks.palette, "Palette et couleur");
+                ui.checkbox(&mut self.locks.coloring, "Coloration");
+                ui.checkbox(&mut self.locks.view, "Zoom et cible");
+            });
+
+        if let Some(seed) = seed {
+            self.roll(seed);
+        }
+        self.show_mystery = open;
+    }
+
+    fn render_rule_editor(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_rule_editor;
+
+        egui::Window::new("Règle de subdivision")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                // Ensemble 1D et escalier : première ligne de la règle
+                ui.horizontal(|ui| {
+                    ui.label("Figure");
+                    egui::ComboBox::from_id_salt("figure_combo")
+                        .selected_text(self.project.figure.label())
+                        .show_ui(ui, |ui| {
+                            for figure in Figure::ALL {
+                                ui.selectable_value(&mut self.project.figure, figure, figure.label());
+                            }
+                        });
+                });
+
+                let mut grid = self.project.rule.grid;
+                ui.horizontal(|ui| {
+                    ui.label("Grille");
+                    ui.add(egui::DragValue::new(&mut grid).range(2..=7).suffix(" × n"));
+                    if ui.button("Cantor").clicked() {
+                        self.project.rule = Rule::cantor();
+                    }
+                });
+                if grid != self.project.rule.grid {
+                    self.project.rule = Rule {
+                        grid,
+                        keep: vec![true; (grid * grid) as usize],
+                    };
+                }
+
+                // Multi-échelle : chaque case a son propre rapport, la grille ne sert plus
+                let mut multi_scale = !self.project.maps.is_empty();
+                if ui.checkbox(&mut multi_scale, "Multi-échelle (homothéties)").changed() {
+                    self.project.maps = if multi_scale { Maps::example() } else { Maps::default() };
+                }
+                if multi_scale {
+                    self.render_maps_editor(ui);
+                    return;
+                }
+
+                // Poussière aléatoire : les cases gardées sont tirées avec la graine du projet
+                let cells = self.project.rule.grid * self.project.rule.grid;
+                ui.horizontal(|ui| {
+                    ui.label("Tirage");
+                    let selected = match self.project.dust {
+                        None => "Masque fixe",
+                        Some(Selection::Probability(_)) => "Probabilité p",
+                        Some(Selection::Pick(_)) => "k cases au hasard",
+                    };
+                    egui::ComboBox::from_id_salt("dust_combo")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            let dust = &mut self.project.dust;
+                            if ui.selectable_label(dust.is_none(), "Masque fixe").clicked() {
+                                *dust = None;
+                            }
+                            let probability = matches!(dust, Some(Selection::Probability(_)));
+                            if ui.selectable_label(probability, "Probabilité p").clicked() && !probability {
+                                *dust = Some(Selection::Probability(0.6));
+                            }
+                            let pick = matches!(dust, Some(Selection::Pick(_)));
+                            if ui.selectable_label(pick, "k cases au hasard").clicked() && !pick {
+                                *dust = Some(Selection::Pick(cells.min(4)));
+                            }
+                        });
+                    match &mut self.project.dust {
+                        Some(Selection::Probability(p)) => {
+                            ui.add(egui::Slider::new(p, 0.05..=1.0).text("p"));
+                        }
+                        Some(Selection::Pick(k)) => {
+                            *k = (*k).min(cells);
+                            ui.add(egui::DragValue::new(k).range(1..=cells).prefix("k = "));
+                        }
+                        None => {}
+                    }
+                });
+
+                ui.add_space(6.0);
+
+                if let Some(dust) = self.project.dust() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Graine {}", dust.seed));
+                        if ui.button("Nouveau tirage").clicked() {
+                            self.project.seed = randomize::new_seed();
+                        }
+                    });
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{:.2} cases gardées par carré en moyenne  ·  dimension attendue {:.4}",
+                            dust.expected_kept(),
+                            dust.expected_dimension()
+                        ))
+                        .color(egui::Color32::from_rgb(120, 120, 120)),
+                    );
+                    return;
+                }
+
+                // Cases cliquables : pleine = gardée
+                let rule = &mut self.project.rule;
+                let cell = 28.0;
+                egui::Grid::new("rule_grid").spacing([2.0, 2.0]).show(ui, |ui| {
+                    for y in 0..rule.grid {
+                        for x in 0..rule.grid {
+                            let index = (y * rule.grid + x) as usize;
+                            let (rect, response) = ui.allocate_exact_size(egui::vec2(cell, cell), egui::Sense::click());
+                            let fill = if rule.keep[index] {
+                                egui::Color32::from_rgb(189, 0, 0)
+                            } else {
+                                egui::Color32::from_rgb(255, 255, 255)
+                            };
+                            ui.painter().rect_filled(rect, 4.0, fill);
+                            ui.painter().rect_stroke(rect, 4.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 200, 200)));
+                            if response.clicked() {
+                                rule.keep[index] = !rule.keep[index];
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.add_space(6.0);
+                ui.label(
+                    egui::RichText::new(format!("{}  ·  dimension {:.4}", rule, stats::dimension(rule)))
+                        .color(egui::Color32::from_rgb(120, 120, 120)),
                 );
-                rectangles.append(&mut sub_rects);
+            });
+
+        self.show_rule_editor = open;
+    }
+
+    /// Liste des homothéties : rapport et coin de chaque image dans le carré unité.
+    fn render_maps_editor(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+        egui::Grid::new("maps_grid")
+            .spacing([8.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["Rapport", "x", "y", ""] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                let count = self.project.maps.0.len();
+                for (i, map) in self.project.maps.0.iter_mut().enumerate() {
+                    ui.add(egui::DragValue::new(&mut map.ratio).speed(0.005).range(0.01..=0.99).max_decimals(4));
+                    // L'image reste dans le carré unité
+                    let max = 1.0 - map.ratio;
+                    for offset in &mut map.offset {
+                        *offset = offset.clamp(0.0, max);
+                        ui.add(egui::DragValue::new(offset).speed(0.005).range(0.0..=max).max_decimals(4));
+                    }
+                    if ui.add_enabled(count > 1, egui::Button::new("✖").small()).clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = remove {
+            self.project.maps.0.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Ajouter").clicked() {
+                self.project.maps.0.push(Map { ratio: 0.25, offset: [0.0, 0.0] });
+            }
+            if ui.button("Exemple 1/2 · 1/4").clicked() {
+                self.project.maps = Maps::example();
+            }
+        });
+
+        ui.add_space(6.0);
+        ui.label(
+            egui::RichText::new(format!(
+                "Σ rᵢ^s = 1  ·  dimension {:.4}",
+                self.project.maps.dimension()
+            ))
+            .color(egui::Color32::from_rgb(120, 120, 120)),
+        );
+    }
+
+    fn render_ifs_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.ifs.open;
+        let mut render = false;
+
+        egui::Window::new("Système de fonctions itérées")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let window = &mut self.ifs;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("ifs_gallery")
+                        .selected_text(&window.system.name)
+                        .show_ui(ui, |ui| {
+                            for system in ifs::gallery() {
+                                if ui.selectable_label(window.system == system, &system.name).clicked() {
+                                    window.system = system;
+                                    render = true;
+                                }
+                            }
+                        });
+                    // Le carré de Cantor du projet, règle ou homothéties
+                    if ui.button("Depuis le projet").clicked() {
+                        window.system = if self.project.maps.is_empty() {
+                            Ifs::from_rule(&self.project.rule)
+                        } else {
+                            Ifs::from_maps(&self.project.maps)
+                        };
+                        render = true;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut window.path).desired_width(160.0));
+                    if ui.button("Charger").clicked() {
+                        match Ifs::load(&window.path) {
+                            Ok(system) => {
+                                window.system = system;
+                                render = true;
+                            }
+                            Err(err) => self.status = format!("Erreur: {}", err),
+                        }
+                    }
+                    if ui.button("Enregistrer").clicked() {
+                        self.status = match window.system.save(&window.path) {
+                            Ok(()) => format!("✓ Système enregistré: {}", window.path),
+                            Err(err) => format!("Erreur: {}", err),
+                        };
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut window.mode, Mode::Chaos, "Jeu du chaos");
+                    ui.radio_value(&mut window.mode, Mode::Deterministic, "Déterministe");
+                });
+                match window.mode {
+                    Mode::Chaos => ui.add(
+                        egui::Slider::new(&mut window.points, 10_000..=10_000_000)
+                            .logarithmic(true)
+                            .text("points"),
+                    ),
+                    Mode::Deterministic => ui.add(egui::Slider::new(&mut window.iterations, 1..=16).text("niveaux")),
+                };
+                ui.add(egui::Slider::new(&mut window.gamma, 0.5..=4.0).text("gamma"));
+
+                ui.horizontal(|ui| {
+                    render |= ui.button("Calculer").clicked();
+                    if ui.add_enabled(window.image.is_some(), egui::Button::new("Exporter PNG")).clicked() {
+                        let entries = window.system.metadata((window.mode == Mode::Chaos).then_some(self.project.seed));
+                        if let Some(image) = &window.image {
+                            self.status = match metadata::save_image(image, "ifs.png", &entries) {
+                                Ok(()) => "✓ Image sauvegardée: ifs.png".to_string(),
+                                Err(err) => format!("Erreur: {}", err),
+                            };
+                        }
+                    }
+                });
+
+                if let Some(texture) = &window.texture {
+                    ui.add_space(6.0);
+                    ui.image((texture.id(), egui::vec2(400.0, 400.0)));
+                }
+            });
+
+        self.ifs.open = open;
+        if open && render {
+            self.render_ifs();
+            let image = self.ifs.image.as_ref().map(|image| self.rgba_to_color_image(image));
+            if let Some(image) = image {
+                self.ifs.texture = Some(ctx.load_texture("ifs", image, egui::TextureOptions::LINEAR));
             }
         }
-        
-        rectangles
-    }
-    fn export_gif(&self, filename: &str) {
-        println!("Génération du GIF...");
-        
-        const WIDTH: usize = 800;  // Taille réduite pour l'export UI
-        const HEIGHT: usize = 800;
-        const NUM_FRAMES: u32 = 23;
-        const ZOOM_SPEED: f32 = 1.05;
-        
-        let mut file = File::create(filename).expect("Erreur création fichier");
-        let mut encoder = Encoder::new(&mut file, WIDTH as u16, HEIGHT as u16, &[])
-            .expect("Erreur création encoder");
-        encoder.set_repeat(Repeat::Infinite).expect("Erreur repeat");
-        
-        let color = self.colors[self.selected_color];
-        let mut zoom = 1.0f32;
-        
-        for frame_num in 0..NUM_FRAMES {
-            println!("Frame {}/{}", frame_num + 1, NUM_FRAMES);
-            
-            let pixels = self.render_gif_frame(WIDTH, HEIGHT, zoom, color);
-            let frame = Frame::from_rgb(WIDTH as u16, HEIGHT as u16, &pixels);
-            encoder.write_frame(&frame).expect("Erreur écriture frame");
-            
-            zoom *= ZOOM_SPEED;
+    }
+
+    /// Rendu du système avec la couleur et la graine du projet.
+    fn render_ifs(&mut self) {
+        let window = &mut self.ifs;
+        if let Err(err) = window.system.validate() {
+            self.status = format!("Erreur: {}", err);
+            return;
         }
-        
-        println!("✓ GIF sauvegardé: {}", filename);
-    }
-
-    fn render_gif_frame(&self, width: usize, height: usize, zoom: f32, color: image::Rgba<u8>) -> Vec<u8> {
-        let size = width.max(height) as u32;
-        let view_size = size as f32 / zoom;
-        
-        // Calculer les itérations dynamiquement
-        let iterations = self.iterations;
-        
-        // Générer l'image complète
-        let mut full_image = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
-        self.draw_cantor_square(&mut full_image, 0, 0, size, iterations, color);
-        
-        // Extraire la région zoomée et convertir en RGB
-        let mut pixels = vec![0u8; width * height * 3];
-        
-        for y in 0..height {
-            for x in 0..width {
-                let src_x = (x as f32 * view_size / width as f32) as u32;
-                let src_y = (y as f32 * view_size / height as f32) as u32;
-                
-                if src_x < size && src_y < size {
-                    let pixel = full_image.get_pixel(src_x, src_y);
-                    let pos = (y * width + x) * 3;
-                    pixels[pos] = pixel[0];
-                    pixels[pos + 1] = pixel[1];
-                    pixels[pos + 2] = pixel[2];
+        let histogram = match window.mode {
+            Mode::Chaos => window.system.chaos_game(800, 800, window.points, self.project.seed),
+            Mode::Deterministic => window.system.deterministic(800, 800, window.iterations),
+        };
+        window.image = Some(histogram.tone_map([255, 255, 255, 255], self.project.color().0, window.gamma));
+    }
+
+    fn render_camera_editor(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_camera_editor;
+
+        egui::Window::new("Chemin de caméra")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut remove = None;
+
+                egui::Grid::new("keyframes_grid")
+                    .spacing([8.0, 6.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Temps", "Centre x", "Centre y", "Zoom", "Rotation", "Easing", ""] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+
+                        // Le chemin garde toujours au moins une keyframe
+                        let removable = self.project.camera.keyframes.len() > 1;
+                        for (i, keyframe) in self.project.camera.keyframes.iter_mut().enumerate() {
+                            ui.add(egui::DragValue::new(&mut keyframe.time).speed(0.01).range(0.0..=f64::MAX));
+                            ui.add(egui::DragValue::new(&mut keyframe.center[0]).speed(0.001).max_decimals(5));
+                            ui.add(egui::DragValue::new(&mut keyframe.center[1]).speed(0.001).max_decimals(5));
+                            let zoom_speed = keyframe.zoom * 0.01;
+                            ui.add(egui::DragValue::new(&mut keyframe.zoom).speed(zoom_speed).range(0.01..=1.0e9));
+                            ui.add(egui::DragValue::new(&mut keyframe.rotation).speed(1.0).suffix("°"));
+
+                            egui::ComboBox::from_id_salt(("easing", i))
+                                .selected_text(keyframe.easing.label())
+                                .show_ui(ui, |ui| {
+                                    for easing in Easing::ALL {
+                                        ui.selectable_value(&mut keyframe.easing, easing, easing.label());
+                                    }
+                                });
+
+                            if ui.add_enabled(removable, egui::Button::new("✖").small()).clicked() {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(i) = remove {
+                    self.project.camera.keyframes.remove(i);
                 }
-            }
+                // Les lignes suivent l'ordre des temps, une fois la saisie finie
+                if ui.ctx().dragged_id().is_none() && ui.memory(|memory| memory.focused().is_none()) {
+                    self.project.camera.sort();
+                }
+
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Ajouter").clicked() {
+                        let keyframe = match self.project.camera.keyframes.last() {
+                            Some(last) => Keyframe { time: last.time + 1.0, ..*last },
+                            None => Keyframe {
+                                time: 0.0,
+                                center: Camera::default().center,
+                                zoom: 1.0,
+                                rotation: 0.0,
+                                easing: Easing::Linear,
+                            },
+                        };
+                        self.project.camera.keyframes.push(keyframe);
+                    }
+                    if ui.button("Réinitialiser").clicked() {
+                        self.project.camera = Project::default().camera;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Fichier");
+                    ui.add(egui::TextEdit::singleline(&mut self.camera_path).desired_width(150.0));
+                    if ui.button("Charger").clicked() {
+                        match CameraPath::load(&self.camera_path) {
+                            Ok(path) => {
+                                self.project.camera = path;
+                                self.status = format!("✓ Chemin chargé: {}", self.camera_path);
+                            }
+                            Err(err) => self.status = format!("Erreur chargement {}: {}", self.camera_path, err),
+                        }
+                        println!("{}", self.status);
+                    }
+                    if ui.button("Enregistrer").clicked() {
+                        self.status = match self.project.camera.save(&self.camera_path) {
+                            Ok(()) => format!("✓ Chemin sauvegardé: {}", self.camera_path),
+                            Err(err) => format!("Erreur sauvegarde {}: {}", self.camera_path, err),
+                        };
+                        println!("{}", self.status);
+                    }
+                });
+
+                ui.add_space(8.0);
+
+                let (start, end) = (self.project.camera.start_time(), self.project.camera.end_time());
+                let mut preview = self.camera_preview.is_some();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut preview, "Aperçu");
+                    let mut time = self.camera_preview.unwrap_or(start).clamp(start, end);
+                    ui.add_enabled(preview, egui::Slider::new(&mut time, start..=end).text("temps"));
+                    self.camera_preview = preview.then_some(time);
+                });
+            });
+
+        self.show_camera_editor = open;
+        if !open {
+            self.camera_preview = None;
         }
-        
-        pixels
     }
+}
 
+/// Libellé d'une modification, pour la liste de l'historique.
+fn describe_change(before: &Project, after: &Project) -> String {
+    let mut changes = Vec::new();
+    if before.rule != after.rule {
+        changes.push(format!("Règle {}", after.rule));
+    }
+    if before.iterations != after.iterations {
+        changes.push(format!("Itérations {} → {}", before.iterations, after.iterations));
+    }
+    if before.zoom != after.zoom {
+        changes.push(format!("Zoom x{} → x{}", before.zoom as i32, after.zoom as i32));
+    }
+    if before.selected_color != after.selected_color || before.palette != after.palette {
+        changes.push("Couleur".to_string());
+    }
+    if before.dust != after.dust || (after.dust.is_some() && before.seed != after.seed) {
+        changes.push("Poussière aléatoire".to_string());
+    }
+    if before.maps != after.maps {
+        changes.push("Multi-échelle".to_string());
+    }
+    if before.figure != after.figure {
+        changes.push(format!("Figure {}", after.figure.label()));
+    }
+    if before.coloring != after.coloring {
+        changes.push(format!("Coloration {}", after.coloring.label()));
+    }
+    if before.camera != after.camera {
+        changes.push("Caméra".to_string());
+    }
+    if before.export != after.export {
+        changes.push("Export".to_string());
+    }
+    changes.join(", ")
 }
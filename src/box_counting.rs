@@ -0,0 +1,256 @@
+//! Box-counting estimate of the fractal dimension of an image.
+//!
+//! The image is covered with square boxes of decreasing size; the dimension
+//! is the slope of `log N(s)` against `log 1/s`, where `N(s)` is the number
+//! of boxes of side `s` that contain at least one filled pixel.
+
+use crate::rule;
+use image::{Rgba, RgbaImage};
+use svg::Document;
+use svg::node::element::{Circle, Line, Rectangle, Text};
+
+/// Number of boxes of one size that touch the set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoxCount {
+    pub size: u32,
+    pub count: u64,
+}
+
+impl BoxCount {
+    /// `log(1 / size)`, abscissa of the log-log plot.
+    pub fn log_scale(&self) -> f64 {
+        (1.0 / self.size as f64).ln()
+    }
+
+    pub fn log_count(&self) -> f64 {
+        (self.count as f64).ln()
+    }
+}
+
+/// Least-squares fit of `log N = dimension × log(1/s) + intercept`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub points: Vec<BoxCount>,
+    pub dimension: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+}
+
+/// Filled pixels of an image: those that differ from the background and are
+/// not fully transparent.
+pub struct Mask {
+    width: u32,
+    height: u32,
+    filled: Vec<bool>,
+}
+
+impl Mask {
+    pub fn new(image: &RgbaImage, background: Rgba<u8>) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            filled: image
+                .pixels()
+                .map(|&pixel| pixel != background && pixel[3] != 0)
+                .collect(),
+        }
+    }
+
+    /// Boxes of side `size` with at least one filled pixel. Boxes on the
+    /// right and bottom edges may be cut by the border of the image.
+    pub fn count(&self, size: u32) -> u64 {
+        let size = size.max(1);
+        let mut count = 0;
+        for by in (0..self.height).step_by(size as usize) {
+            for bx in (0..self.width).step_by(size as usize) {
+                let touches = (by..(by + size).min(self.height)).any(|y| {
+                    let row = y as usize * self.width as usize;
+                    self.filled[row + bx as usize..row + (bx + size).min(self.width) as usize]
+                        .contains(&true)
+                });
+                if touches {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+/// Box sizes `base^k` from `min_size` up to a quarter of the smallest side,
+/// so that the largest boxes still cut the image in a few pieces.
+pub fn geometric_sizes(width: u32, height: u32, base: u32, min_size: u32) -> Vec<u32> {
+    let base = base.max(2);
+    let max_size = (width.min(height) / 4).max(1);
+    let mut sizes = Vec::new();
+    let mut size = min_size.max(1);
+    while size <= max_size {
+        sizes.push(size);
+        size = match size.checked_mul(base) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    sizes
+}
+
+/// Box sizes for a `size`-pixel drawing of a `grid × grid` construction:
+/// powers of the grid, so that the boxes follow the squares, starting at the
+/// squares of the last iteration drawn. Smaller boxes would all count the
+/// same squares.
+pub fn construction_sizes(size: u32, grid: u32, iterations: u32) -> Vec<u32> {
+    let depth = iterations.min(rule::depth_limit(grid, size as f64));
+    geometric_sizes(size, size, grid, (size / grid.max(2).pow(depth)).max(1))
+}
+
+/// Counts the boxes for each size and fits the log-log line.
+///
+/// Sizes where no box is filled are left out of the fit.
+pub fn estimate(image: &RgbaImage, background: Rgba<u8>, sizes: &[u32]) -> Estimate {
+    let mask = Mask::new(image, background);
+    let points: Vec<BoxCount> = sizes
+        .iter()
+        .map(|&size| BoxCount { size, count: mask.count(size) })
+        .filter(|point| point.count > 0)
+        .collect();
+
+    let (dimension, intercept, r_squared) = linear_fit(
+        &points.iter().map(|point| (point.log_scale(), point.log_count())).collect::<Vec<_>>(),
+    );
+    Estimate { points, dimension, intercept, r_squared }
+}
+
+/// Slope, intercept and coefficient of determination of `y = a x + b`.
+fn linear_fit(points: &[(f64, f64)]) -> (f64, f64, f64) {
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    // Every point on the line (constant counts included): perfect fit
+    let r_squared = if syy == 0.0 { 1.0 } else { sxy * sxy / (sxx * syy) };
+    (slope, intercept, r_squared)
+}
+
+impl Estimate {
+    /// Data points as CSV, one line per box size.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("taille,boites,log_inverse_taille,log_boites\n");
+        for point in &self.points {
+            csv.push_str(&format!(
+                "{},{},{:.6},{:.6}\n",
+                point.size,
+                point.count,
+                point.log_scale(),
+                point.log_count()
+            ));
+        }
+        csv
+    }
+
+    /// Log-log plot of the data points and of the fitted line.
+    pub fn plot(&self) -> Document {
+        let (width, height, margin) = (640.0, 480.0, 48.0);
+        let xs = self.points.iter().map(BoxCount::log_scale);
+        let ys = self.points.iter().map(BoxCount::log_count);
+        let (min_x, max_x) = bounds(xs);
+        let (min_y, max_y) = bounds(ys);
+        let to_screen = |x: f64, y: f64| {
+            (
+                margin + (x - min_x) / (max_x - min_x) * (width - 2.0 * margin),
+                height - margin - (y - min_y) / (max_y - min_y) * (height - 2.0 * margin),
+            )
+        };
+
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height))
+            .add(
+                Rectangle::new()
+                    .set("width", width)
+                    .set("height", height)
+                    .set("fill", "white"),
+            );
+
+        // Axes
+        for (x1, y1, x2, y2) in [
+            (margin, height - margin, width - margin, height - margin),
+            (margin, margin, margin, height - margin),
+        ] {
+            document = document.add(
+                Line::new()
+                    .set("x1", x1)
+                    .set("y1", y1)
+                    .set("x2", x2)
+                    .set("y2", y2)
+                    .set("stroke", "#282828"),
+            );
+        }
+
+        if self.dimension.is_finite() {
+            let (x1, y1) = to_screen(min_x, self.dimension * min_x + self.intercept);
+            let (x2, y2) = to_screen(max_x, self.dimension * max_x + self.intercept);
+            document = document.add(
+                Line::new()
+                    .set("x1", x1)
+                    .set("y1", y1)
+                    .set("x2", x2)
+                    .set("y2", y2)
+                    .set("stroke", "#ef476f")
+                    .set("stroke-width", 2),
+            );
+        }
+
+        for point in &self.points {
+            let (cx, cy) = to_screen(point.log_scale(), point.log_count());
+            document = document.add(
+                Circle::new()
+                    .set("cx", cx)
+                    .set("cy", cy)
+                    .set("r", 4)
+                    .set("fill", "#118ab2"),
+            );
+        }
+
+        let caption = format!("D ≈ {:.4}   R² = {:.5}", self.dimension, self.r_squared);
+        let labels = [
+            (width / 2.0, height - 12.0, "middle", "log(1 / taille)".to_string()),
+            (margin, 28.0, "start", "log N".to_string()),
+            (width - margin, 28.0, "end", caption),
+        ];
+        for (x, y, anchor, label) in labels {
+            document = document.add(
+                Text::new(label)
+                    .set("x", x)
+                    .set("y", y)
+                    .set("text-anchor", anchor)
+                    .set("font-family", "sans-serif")
+                    .set("font-size", 14),
+            );
+        }
+
+        document
+    }
+}
+
+/// Range of the values, widened when they are all equal.
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if !min.is_finite() {
+        (0.0, 1.0)
+    } else if max - min < 1e-9 {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    }
+}
@@ -0,0 +1,255 @@
+//! Keyframed camera paths for zoom animations.
+//!
+//! World coordinates are those of the unit square holding the fractal. A
+//! camera with zoom `z` shows a viewport `1 / z` wide.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseInOut,
+    Exponential,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 3] = [Easing::Linear, Easing::EaseInOut, Easing::Exponential];
+
+    /// Maps a segment progress `t` in `[0, 1]` to an eased progress.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Exponential => {
+                if t == 0.0 {
+                    0.0
+                } else {
+                    2f64.powf(10.0 * (t - 1.0))
+                }
+            }
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Easing::Linear => "Linéaire",
+            Easing::EaseInOut => "Douce",
+            Easing::Exponential => "Exponentielle",
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "linear" => Ok(Easing::Linear),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            "exponential" => Ok(Easing::Exponential),
+            _ => Err(format!("easing inconnu: {}", value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub center: [f64; 2],
+    pub zoom: f64,
+    /// Rotation of the view in degrees.
+    pub rotation: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: [0.5, 0.5],
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Camera {
+    /// Maps a pixel position of a `width × height` frame to world coordinates.
+    pub fn screen_to_world(&self, px: f64, py: f64, width: usize, height: usize) -> [f64; 2] {
+        let view_width = 1.0 / self.zoom;
+        let view_height = view_width * height as f64 / width as f64;
+        let u = (px / width as f64 - 0.5) * view_width;
+        let v = (py / height as f64 - 0.5) * view_height;
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        [
+            self.center[0] + u * cos - v * sin,
+            self.center[1] + u * sin + v * cos,
+        ]
+    }
+
+    /// Size of one pixel in world units.
+    pub fn pixel_size(&self, width: usize) -> f64 {
+        1.0 / (self.zoom * width as f64)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    pub center: [f64; 2],
+    pub zoom: f64,
+    #[serde(default)]
+    pub rotation: f64,
+    /// Easing of the segment that starts at this keyframe.
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn camera(&self) -> Camera {
+        Camera {
+            center: self.center,
+            zoom: self.zoom,
+            rotation: self.rotation,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keyframes }
+    }
+
+    /// The classic zoom: `frames` frames, each `speed` times closer to `center`.
+    pub fn exponential_zoom(center: [f64; 2], start_zoom: f64, speed: f64, frames: u32) -> Self {
+        let steps = frames.saturating_sub(1) as i32;
+        Self::new(vec![
+            Keyframe {
+                time: 0.0,
+                center,
+                zoom: start_zoom,
+                rotation: 0.0,
+                easing: Easing::Linear,
+            },
+            Keyframe {
+                time: 1.0,
+                center,
+                zoom: start_zoom * speed.powi(steps),
+                rotation: 0.0,
+                easing: Easing::Linear,
+            },
+        ])
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path: CameraPath = toml::from_str(&fs::read_to_string(path)?)?;
+        path.validate()?;
+        Ok(Self::new(path.keyframes))
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.keyframes.is_empty() {
+            return Err("le chemin de caméra ne contient aucune keyframe".into());
+        }
+        if self.keyframes.iter().any(|k| !k.time.is_finite()) {
+            return Err("le temps d'une keyframe doit être un nombre fini".into());
+        }
+        if self.keyframes.iter().any(|k| !(k.zoom > 0.0 && k.zoom.is_finite())) {
+            return Err("le zoom d'une keyframe doit être positif".into());
+        }
+        if self.keyframes.iter().any(|k| !(k.center.iter().all(|c| c.is_finite()) && k.rotation.is_finite())) {
+            return Err("le centre et la rotation d'une keyframe doivent être des nombres finis".into());
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn start_time(&self) -> f64 {
+        self.keyframes.iter().map(|k| k.time).reduce(f64::min).unwrap_or(0.0)
+    }
+
+    pub fn end_time(&self) -> f64 {
+        self.keyframes.iter().map(|k| k.time).reduce(f64::max).unwrap_or(0.0)
+    }
+
+    /// Interpolates the camera at `time`, clamped to the path. Keyframes out
+    /// of order, as left by an edit, are sampled as if sorted.
+    ///
+    /// Zoom is interpolated geometrically and the center linearly in viewport
+    /// width, so a path between two views sharing a fixed point keeps it fixed.
+    pub fn sample(&self, time: f64) -> Camera {
+        if !self.keyframes.is_sorted_by(|a, b| a.time.total_cmp(&b.time).is_le()) {
+            let mut sorted = self.clone();
+            sorted.sort();
+            return sorted.sample(time);
+        }
+        let (first, last) = match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Camera::default(),
+        };
+        if time <= first.time {
+            return first.camera();
+        }
+        if time >= last.time {
+            return last.camera();
+        }
+
+        let i = self.keyframes.partition_point(|k| k.time <= time) - 1;
+        let (a, b) = (&self.keyframes[i], &self.keyframes[i + 1]);
+        let span = b.time - a.time;
+        let t = if span > 0.0 { (time - a.time) / span } else { 1.0 };
+        let t = a.easing.apply(t);
+
+        let zoom = a.zoom * (b.zoom / a.zoom).powf(t);
+        let (wa, wb, w) = (1.0 / a.zoom, 1.0 / b.zoom, 1.0 / zoom);
+        let s = if (wa - wb).abs() > f64::EPSILON * wa {
+            (wa - w) / (wa - wb)
+        } else {
+            t
+        };
+
+        Camera {
+            center: [
+                a.center[0] + (b.center[0] - a.center[0]) * s,
+                a.center[1] + (b.center[1] - a.center[1]) * s,
+            ],
+            zoom,
+            rotation: a.rotation + (b.rotation - a.rotation) * t,
+        }
+    }
+
+    /// Camera of frame `index` out of `count` frames evenly spaced from the
+    /// first to the last keyframe.
+    pub fn frame(&self, index: u32, count: u32) -> Camera {
+        let t = if count > 1 {
+            index as f64 / (count - 1) as f64
+        } else {
+            0.0
+        };
+        let (start, end) = (self.start_time(), self.end_time());
+        self.sample(start + (end - start) * t)
+    }
+
+    /// Samples `count` cameras evenly spaced from the first to the last keyframe.
+    pub fn frames(&self, count: u32) -> impl Iterator<Item = Camera> + '_ {
+        (0..count).map(move |i| self.frame(i, count))
+    }
+}
@@ -0,0 +1,133 @@
+//! Minimal command-line parsing shared by the binaries.
+//!
+//! Options are written `--name value` or `--name=value`. An option that is
+//! followed by another option (or by nothing) is treated as a flag.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub struct Args {
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Parses the arguments of the current process (without the program name).
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut options = HashMap::new();
+        let mut flags = Vec::new();
+        let mut positional = Vec::new();
+
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+
+            if let Some((name, value)) = name.split_once('=') {
+                options.insert(name.to_string(), value.to_string());
+            } else if args.peek().is_some_and(|next| !next.starts_with("--")) {
+                options.insert(name.to_string(), args.next().unwrap());
+            } else {
+                flags.push(name.to_string());
+            }
+        }
+
+        Self {
+            options,
+            flags,
+            positional,
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Parses the value of `--name` with [`FromStr`], if present.
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.get_with(name, |value| {
+            value
+                .parse()
+                .map_err(|_| format!("valeur invalide: {}", value))
+        })
+    }
+
+    /// Parses the value of `--name` with a custom parser, if present.
+    pub fn get_with<T>(
+        &self,
+        name: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        match self.value(name) {
+            Some(value) => parse(value)
+                .map(Some)
+                .map_err(|err| format!("--{}: {}", name, err)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parses a comma-separated list such as `0.33,0.33`.
+pub fn parse_list<T: FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|part| {
+            part.trim()
+                .parse()
+                .map_err(|_| format!("valeur invalide: {}", part))
+        })
+        .collect()
+}
+
+/// Parses a pair `x,y`.
+pub fn parse_pair<T: FromStr + Copy>(value: &str) -> Result<[T; 2], String> {
+    match parse_list(value)?.as_slice() {
+        [x, y] => Ok([*x, *y]),
+        _ => Err(format!("deux valeurs attendues: {}", value)),
+    }
+}
+
+/// Parses a color written `r,g,b` or `#rrggbb`.
+pub fn parse_rgb(value: &str) -> Result<[u8; 3], String> {
+    match parse_channels(value)?.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b]),
+        _ => Err(format!("couleur invalide: {}", value)),
+    }
+}
+
+/// Parses a color written `r,g,b[,a]` or `#rrggbb[aa]`; alpha defaults to 255.
+pub fn parse_rgba(value: &str) -> Result<[u8; 4], String> {
+    match parse_channels(value)?.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b, 255]),
+        [r, g, b, a] => Ok([*r, *g, *b, *a]),
+        _ => Err(format!("couleur invalide: {}", value)),
+    }
+}
+
+fn parse_channels(value: &str) -> Result<Vec<u8>, String> {
+    let Some(hex) = value.strip_prefix('#') else {
+        return parse_list(value);
+    };
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(format!("couleur invalide: {}", value));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("couleur invalide: {}", value)))
+        .collect()
+}
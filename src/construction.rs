@@ -0,0 +1,147 @@
+//! Construction animation of the Cantor square, iteration by iteration.
+//!
+//! Between two iterations, tween frames either fade the removed middle bands
+//! into the background or shrink them until the 4 corners are apart.
+
+use crate::camera::Easing;
+use crate::rule::{self, Rule};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    #[default]
+    Fade,
+    Shrink,
+}
+
+impl FromStr for Transition {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fade" | "fondu" => Ok(Transition::Fade),
+            "shrink" | "retrait" => Ok(Transition::Shrink),
+            _ => Err(format!("transition inconnue: {}", value)),
+        }
+    }
+}
+
+/// One frame of the animation: `level` on its way to `level + 1`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    pub level: u32,
+    /// Eased progress in `[0, 1)`; 0 is the plain iteration `level`.
+    pub progress: f64,
+}
+
+impl Step {
+    pub fn is_tween(&self) -> bool {
+        self.progress > 0.0
+    }
+}
+
+/// Lists the frames from iteration 0 to `max_iterations`, with `tweens`
+/// frames between two iterations. With `ping_pong` the construction is then
+/// played backwards, without repeating the first and last frames.
+pub fn schedule(max_iterations: u32, tweens: u32, easing: Easing, ping_pong: bool) -> Vec<Step> {
+    let mut steps = Vec::new();
+    for level in 0..max_iterations {
+        steps.push(Step { level, progress: 0.0 });
+        for i in 1..=tweens {
+            let t = i as f64 / (tweens + 1) as f64;
+            steps.push(Step {
+                level,
+                progress: easing.apply(t),
+            });
+        }
+    }
+    steps.push(Step {
+        level: max_iterations,
+        progress: 0.0,
+    });
+
+    if ping_pong && steps.len() > 2 {
+        let back: Vec<Step> = steps[1..steps.len() - 1].iter().rev().copied().collect();
+        steps.extend(back);
+    }
+
+    steps
+}
+
+/// Draws one frame of the construction in a `size × size` square at the origin.
+pub fn draw_step(
+    image: &mut RgbaImage,
+    size: u32,
+    step: Step,
+    transition: Transition,
+    color: Rgba<u8>,
+    background: Rgba<u8>,
+) {
+    let cantor = Rule::cantor();
+    if !step.is_tween() {
+        cantor.draw(image, 0, 0, size, step.level, color);
+        return;
+    }
+
+    match transition {
+        Transition::Fade => {
+            let faded = blend(color, background, step.progress);
+            cantor.draw(image, 0, 0, size, step.level, faded);
+            cantor.draw(image, 0, 0, size, step.level + 1, color);
+        }
+        Transition::Shrink => {
+            draw_shrinking_square(image, 0, 0, size, step.level, step.progress, color);
+        }
+    }
+}
+
+/// Like [`Rule::draw`] with the Cantor square, but the last subdivision only
+/// opens `progress` of the way: the corners start half as wide as the square.
+fn draw_shrinking_square(
+    image: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    size: u32,
+    iterations: u32,
+    progress: f64,
+    color: Rgba<u8>,
+) {
+    let sub_size = size / 3;
+    if sub_size == 0 {
+        rule::fill_square(image, x, y, size, color);
+        return;
+    }
+
+    if iterations == 0 {
+        let half = (3 * sub_size).div_ceil(2);
+        let corner = sub_size + ((half - sub_size) as f64 * (1.0 - progress)).round() as u32;
+        for i in [0, 3 * sub_size - corner] {
+            for j in [0, 3 * sub_size - corner] {
+                rule::fill_square(image, x + i, y + j, corner, color);
+            }
+        }
+        return;
+    }
+
+    for i in [0, 2].iter() {
+        for j in [0, 2].iter() {
+            draw_shrinking_square(
+                image,
+                x + i * sub_size,
+                y + j * sub_size,
+                sub_size,
+                iterations - 1,
+                progress,
+                color,
+            );
+        }
+    }
+}
+
+fn blend(from: Rgba<u8>, to: Rgba<u8>, t: f64) -> Rgba<u8> {
+    let channel = |i: usize| (from[i] as f64 + (to[i] as f64 - from[i] as f64) * t).round() as u8;
+    Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
@@ -0,0 +1,202 @@
+//! Random Cantor dust: each square keeps cells drawn at random instead of
+//! following a fixed mask.
+//!
+//! The cells kept in a square only depend on the seed and on the position of
+//! the square, so every renderer (recursive drawing, SVG, per-pixel frames)
+//! draws the same dust.
+
+use crate::randomize::Seed;
+use crate::rule;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the kept cells of each square are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Selection {
+    /// Each cell is kept with probability `p` (fractal percolation).
+    Probability(f64),
+    /// `k` cells picked at random among the `grid × grid`.
+    Pick(u32),
+}
+
+impl Selection {
+    pub fn validate(&self, grid: u32) -> Result<(), String> {
+        match *self {
+            Selection::Probability(p) if !(p > 0.0 && p <= 1.0) => {
+                Err(format!("probabilité hors de ]0, 1]: {}", p))
+            }
+            Selection::Pick(k) if k == 0 || k > grid * grid => {
+                Err(format!("nombre de cases hors de 1..={}: {}", grid * grid, k))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Written `p=0.6` or `k=4`, like on the command line.
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Selection::Probability(p) => write!(f, "p={}", p),
+            Selection::Pick(k) => write!(f, "k={}", k),
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("tirage invalide: {} (p=0.6 ou k=4)", value);
+        match value.trim().split_once('=') {
+            Some(("p", p)) => p.trim().parse().map(Selection::Probability).map_err(|_| invalid()),
+            Some(("k", k)) => k.trim().parse().map(Selection::Pick).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dust {
+    pub grid: u32,
+    pub selection: Selection,
+    pub seed: Seed,
+}
+
+impl Dust {
+    /// Keep mask of the square at `cell` (in units of its own side) of
+    /// iteration `level`, row-major like [`rule::Rule::keep`].
+    pub fn mask(&self, level: u32, cell: [u64; 2]) -> Vec<bool> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.square_seed(level, cell));
+        let cells = (self.grid * self.grid) as usize;
+        match self.selection {
+            Selection::Probability(p) => (0..cells).map(|_| rng.random_bool(p.clamp(0.0, 1.0))).collect(),
+            Selection::Pick(k) => {
+                let mut keep = vec![false; cells];
+                for index in rand::seq::index::sample(&mut rng, cells, (k as usize).min(cells)) {
+                    keep[index] = true;
+                }
+                keep
+            }
+        }
+    }
+
+    /// Mixes the seed and the position of a square (SplitMix64 steps).
+    fn square_seed(&self, level: u32, [x, y]: [u64; 2]) -> u64 {
+        [level as u64, x, y].iter().fold(self.seed as u64, |hash, &value| {
+            let mut z = (hash ^ value).wrapping_add(0x9E37_79B9_7F4A_7C15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        })
+    }
+
+    /// Expected number of cells kept in a square.
+    pub fn expected_kept(&self) -> f64 {
+        match self.selection {
+            Selection::Probability(p) => p * (self.grid * self.grid) as f64,
+            Selection::Pick(k) => k as f64,
+        }
+    }
+
+    /// Expected number of squares after `iterations` steps.
+    pub fn expected_squares(&self, iterations: u32) -> f64 {
+        self.expected_kept().powf(iterations as f64)
+    }
+
+    /// Dimension of the limit set: `log(k) / log(grid)`, and for percolation
+    /// `log(p grid²) / log(grid)` when the dust survives. With `p grid² ≤ 1`
+    /// it dies out almost surely.
+    pub fn expected_dimension(&self) -> f64 {
+        let kept = self.expected_kept();
+        if kept <= 1.0 {
+            0.0
+        } else {
+            kept.ln() / (self.grid as f64).ln()
+        }
+    }
+
+    /// Number of squares of the dust drawn in a `size`-pixel square. Like
+    /// [`Dust::squares`], `iterations` is clamped to squares of one pixel, so
+    /// the count never visits more squares than the drawing has pixels.
+    pub fn count(&self, size: u32, iterations: u32) -> u64 {
+        let iterations = iterations.min(rule::depth_limit(self.grid, size as f64));
+        self.count_from(0, [0, 0], iterations)
+    }
+
+    fn count_from(&self, level: u32, cell: [u64; 2], iterations: u32) -> u64 {
+        if level == iterations {
+            return 1;
+        }
+        self.mask(level, cell)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, keep)| keep)
+            .map(|(index, _)| self.count_from(level + 1, self.child(cell, index), iterations))
+            .sum()
+    }
+
+    /// Position of the cell `index` of the square at `cell`, one level down.
+    fn child(&self, [x, y]: [u64; 2], index: usize) -> [u64; 2] {
+        let grid = self.grid as u64;
+        let (i, j) = (index as u64 % grid, index as u64 / grid);
+        [x.wrapping_mul(grid).wrapping_add(i), y.wrapping_mul(grid).wrapping_add(j)]
+    }
+
+    /// Calls `visit(x, y, side, path)` for each square of the dust in a
+    /// `size × size` square at `(x, y)`, like [`rule::Rule::squares`]: `path`
+    /// holds the rank of the chosen cell among the kept cells at each level.
+    pub fn squares(
+        &self,
+        x: u32,
+        y: u32,
+        size: u32,
+        iterations: u32,
+        visit: &mut impl FnMut(u32, u32, u32, &[u32]),
+    ) {
+        if size == 0 {
+            return;
+        }
+        let iterations = iterations.min(rule::depth_limit(self.grid, size as f64));
+        let mut path = Vec::with_capacity(iterations as usize);
+        self.visit_squares([x, y], size, [0, 0], 0, iterations, &mut path, visit);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn visit_squares(
+        &self,
+        [x, y]: [u32; 2],
+        size: u32,
+        cell: [u64; 2],
+        level: u32,
+        iterations: u32,
+        path: &mut Vec<u32>,
+        visit: &mut impl FnMut(u32, u32, u32, &[u32]),
+    ) {
+        if level == iterations {
+            visit(x, y, size, path);
+            return;
+        }
+
+        let (grid, sub_size) = (self.grid, size / self.grid);
+        let kept = self.mask(level, cell).into_iter().enumerate().filter(|&(_, keep)| keep);
+        for (rank, (index, _)) in kept.enumerate() {
+            let (i, j) = (index as u32 % grid, index as u32 / grid);
+            path.push(rank as u32);
+            self.visit_squares(
+                [x + i * sub_size, y + j * sub_size],
+                sub_size,
+                self.child(cell, index),
+                level + 1,
+                iterations,
+                path,
+                visit,
+            );
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Raster formats of the still exports, chosen from the file extension,
+//! with the options of each format.
+//!
+//! Only PNG and JPEG carry the [`metadata`] entries; the other formats are
+//! written without them.
+
+use crate::metadata;
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+use image::codecs::qoi::QoiEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ColorType, DynamicImage, ImageBuffer, ImageEncoder, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Tiff,
+    Bmp,
+    /// Lossless WebP (VP8L).
+    Webp,
+    Qoi,
+    /// Binary RGB pixmap (P6).
+    Ppm,
+    /// Binary graymap (P5), the luma of the image.
+    Pgm,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 8] = [
+        ImageFormat::Png,
+        ImageFormat::Jpeg,
+        ImageFormat::Tiff,
+        ImageFormat::Bmp,
+        ImageFormat::Webp,
+        ImageFormat::Qoi,
+        ImageFormat::Ppm,
+        ImageFormat::Pgm,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Tiff => "TIFF",
+            ImageFormat::Bmp => "BMP",
+            ImageFormat::Webp => "WebP sans perte",
+            ImageFormat::Qoi => "QOI",
+            ImageFormat::Ppm => "PPM",
+            ImageFormat::Pgm => "PGM (gris)",
+        }
+    }
+
+    /// Usual extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Tiff => "tif",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Qoi => "qoi",
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Pgm => "pgm",
+        }
+    }
+
+    /// Format of `path` from its extension; an unknown or missing extension
+    /// is an error rather than a guess.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let known: Vec<&str> = Self::ALL.iter().map(|format| format.extension()).collect();
+        match metadata::extension(path) {
+            Some(extension) => extension.parse().map_err(|_| {
+                format!("format d'image non reconnu: .{} ({})", extension, known.join(", "))
+            }),
+            None => Err(format!("extension manquante: {} ({})", path.display(), known.join(", "))),
+        }
+    }
+
+    /// Whether the 16-bit option applies.
+    pub fn has_sixteen_bit(self) -> bool {
+        matches!(self, ImageFormat::Png | ImageFormat::Tiff)
+    }
+
+    /// Whether the alpha option applies; the others are always opaque.
+    pub fn has_alpha(self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Png | ImageFormat::Tiff | ImageFormat::Bmp | ImageFormat::Webp | ImageFormat::Qoi
+        )
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+            "tif" | "tiff" => Ok(ImageFormat::Tiff),
+            "bmp" => Ok(ImageFormat::Bmp),
+            "webp" => Ok(ImageFormat::Webp),
+            "qoi" => Ok(ImageFormat::Qoi),
+            "ppm" => Ok(ImageFormat::Ppm),
+            "pgm" => Ok(ImageFormat::Pgm),
+            _ => Err(format!("format d'image inconnu: {} (png, jpg, tif, bmp, webp, qoi, ppm, pgm)", value)),
+        }
+    }
+}
+
+/// Deflate effort of the PNG exports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [PngCompression::Fast, PngCompression::Default, PngCompression::Best];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngCompression::Fast => "Rapide",
+            PngCompression::Default => "Normale",
+            PngCompression::Best => "Maximale",
+        }
+    }
+
+    pub(crate) fn png(self) -> png::Compression {
+        match self {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        }
+    }
+}
+
+impl fmt::Display for PngCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PngCompression::Fast => "fast",
+            PngCompression::Default => "default",
+            PngCompression::Best => "best",
+        })
+    }
+}
+
+impl FromStr for PngCompression {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "fast" | "rapide" => Ok(PngCompression::Fast),
+            "default" | "normale" => Ok(PngCompression::Default),
+            "best" | "maximale" => Ok(PngCompression::Best),
+            _ => Err(format!("compression PNG inconnue: {} (fast, default, best)", value)),
+        }
+    }
+}
+
+/// Options of the still exports, each used by the formats it applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageOptions {
+    /// JPEG quality, 1 to 100.
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    /// 16 bits per channel in PNG and TIFF; the 8-bit values are scaled.
+    pub sixteen_bit: bool,
+    /// Alpha channel where the format has one. Without it, translucent
+    /// colors are blended onto white.
+    pub alpha: bool,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: 75,
+            png_compression: PngCompression::Default,
+            sixteen_bit: false,
+            alpha: true,
+        }
+    }
+}
+
+impl ImageOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.jpeg_quality) {
+            return Err(format!("qualité JPEG hors de 1..=100: {}", self.jpeg_quality));
+        }
+        Ok(())
+    }
+}
+
+/// Samples of `image` as 8-bit RGBA, or RGB blended onto white.
+fn samples(image: &RgbaImage, alpha: bool) -> (Vec<u8>, ColorType) {
+    if alpha {
+        return (image.as_raw().clone(), ColorType::Rgba8);
+    }
+    let rgb = image
+        .pixels()
+        .flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            [r, g, b].map(|c| ((c as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8)
+        })
+        .collect();
+    (rgb, ColorType::Rgb8)
+}
+
+/// Saves `image` in the format given by the extension of `path`, with the
+/// entries when the format has room for them.
+pub fn save(
+    image: &RgbaImage,
+    path: impl AsRef<Path>,
+    entries: &[(String, String)],
+    options: &ImageOptions,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path)?;
+    options.validate()?;
+    let (width, height) = image.dimensions();
+    let alpha = options.alpha && format.has_alpha();
+    let mut file = BufWriter::new(File::create(path)?);
+    let (data, color) = samples(image, alpha);
+    match format {
+        ImageFormat::Png => metadata::write_png(&mut file, &data, color, width, height, entries, options)?,
+        ImageFormat::Jpeg => metadata::write_jpeg(&mut file, &data, width, height, entries, options.jpeg_quality)?,
+        ImageFormat::Tiff if options.sixteen_bit => {
+            // 255 becomes 65535; u16 buffers, so the encoder's byte view is aligned
+            let wide: Vec<u16> = data.iter().map(|&c| c as u16 * 257).collect();
+            let image = if alpha {
+                ImageBuffer::from_raw(width, height, wide).map(DynamicImage::ImageRgba16)
+            } else {
+                ImageBuffer::from_raw(width, height, wide).map(DynamicImage::ImageRgb16)
+            };
+            image
+                .ok_or("échantillons 16 bits incomplets")?
+                .write_to(&mut file, image::ImageOutputFormat::Tiff)?;
+        }
+        ImageFormat::Tiff => TiffEncoder::new(&mut file).encode(&data, width, height, color)?,
+        ImageFormat::Bmp => BmpEncoder::new(&mut file).encode(&data, width, height, color)?,
+        ImageFormat::Webp => WebPEncoder::new_lossless(&mut file).encode(&data, width, height, color)?,
+        ImageFormat::Qoi => QoiEncoder::new(&mut file).write_image(&data, width, height, color)?,
+        ImageFormat::Ppm => PnmEncoder::new(&mut file)
+            .with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary))
+            .encode(data.as_slice(), width, height, ColorType::Rgb8)?,
+        ImageFormat::Pgm => {
+            // Rec. 601 luma, like image's own conversion
+            let gray: Vec<u8> = data
+                .chunks_exact(3)
+                .map(|rgb| ((rgb[0] as u32 * 299 + rgb[1] as u32 * 587 + rgb[2] as u32 * 114 + 500) / 1000) as u8)
+                .collect();
+            PnmEncoder::new(&mut file)
+                .with_subtype(PnmSubtype::Graymap(SampleEncoding::Binary))
+                .encode(gray.as_slice(), width, height, ColorType::L8)?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
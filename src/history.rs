@@ -0,0 +1,77 @@
+//! Undo/redo history of snapshots.
+//!
+//! Each entry keeps the state before a change and a short label of the
+//! change, so that a list of the past (and undone) changes can be shown.
+
+pub struct History<T> {
+    current: T,
+    undo: Vec<(String, T)>,
+    redo: Vec<(String, T)>,
+    limit: usize,
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    /// Keeps at most `limit` changes to undo.
+    pub fn new(state: T, limit: usize) -> Self {
+        Self {
+            current: state,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    /// Last recorded state.
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Records `state` as a new change, unless it is the current state.
+    /// Changes that were undone can no longer be redone afterwards.
+    pub fn record(&mut self, state: &T, label: impl Into<String>) -> bool {
+        if *state == self.current {
+            return false;
+        }
+        let previous = std::mem::replace(&mut self.current, state.clone());
+        self.undo.push((label.into(), previous));
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// Goes back one change and returns the restored state.
+    pub fn undo(&mut self) -> Option<&T> {
+        let (label, previous) = self.undo.pop()?;
+        let next = std::mem::replace(&mut self.current, previous);
+        self.redo.push((label, next));
+        Some(&self.current)
+    }
+
+    /// Applies again the last undone change.
+    pub fn redo(&mut self) -> Option<&T> {
+        let (label, next) = self.redo.pop()?;
+        let previous = std::mem::replace(&mut self.current, next);
+        self.undo.push((label, previous));
+        Some(&self.current)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Labels of the changes that can be undone, oldest first.
+    pub fn done(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|(label, _)| label.as_str())
+    }
+
+    /// Labels of the undone changes, next to redo first.
+    pub fn undone(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().rev().map(|(label, _)| label.as_str())
+    }
+}
@@ -0,0 +1,504 @@
+//! Iterated function systems: the attractor of a list of affine
+//! contractions, drawn by deterministic iteration or by the chaos game into a
+//! density histogram.
+//!
+//! The Cantor square is the attractor of its 4 corner maps; any rule or list
+//! of multi-scale maps converts to a system, next to the Barnsley fern and
+//! the Sierpiński triangle. Systems are saved as TOML:
+//!
+//! ```toml
+//! name = "Fougère"
+//!
+//! [[maps]]
+//! matrix = [[0.85, 0.04], [-0.04, 0.85]]
+//! offset = [0.0, 1.6]
+//! probability = 0.85
+//! ```
+
+use crate::affine::Maps;
+use crate::metadata::{self, Entries, entry};
+use crate::randomize::{self, Seed};
+use crate::rule::Rule;
+use image::RgbaImage;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Images of the unit square in a deterministic drawing, 64 MiB of
+/// [`Transform`]s: the 16 levels of the interface would reach 4¹⁶ for the fern.
+pub const MAX_SHAPES: usize = 1 << 20;
+
+/// Affine map `p ↦ matrix · p + offset`, with `y` pointing up.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transform {
+    /// Rows of the linear part.
+    pub matrix: [[f64; 2]; 2],
+    pub offset: [f64; 2],
+    /// Weight in the chaos game; by default the area ratio `|det|`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probability: Option<f64>,
+}
+
+impl Transform {
+    /// Similarity of ratio `ratio` sending the unit square to the one at
+    /// `offset`.
+    pub fn scaling(ratio: f64, offset: [f64; 2]) -> Self {
+        Self {
+            matrix: [[ratio, 0.0], [0.0, ratio]],
+            offset,
+            probability: None,
+        }
+    }
+
+    pub fn apply(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let [[a, b], [c, d]] = self.matrix;
+        [a * x + b * y + self.offset[0], c * x + d * y + self.offset[1]]
+    }
+
+    /// `self ∘ inner`: applies `inner` first.
+    pub fn compose(&self, inner: &Transform) -> Transform {
+        let [[a, b], [c, d]] = self.matrix;
+        let [[e, f], [g, h]] = inner.matrix;
+        Transform {
+            matrix: [[a * e + b * g, a * f + b * h], [c * e + d * g, c * f + d * h]],
+            offset: self.apply(inner.offset),
+            probability: None,
+        }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let [[a, b], [c, d]] = self.matrix;
+        a * d - b * c
+    }
+
+    /// Largest stretch of the linear part (its operator norm).
+    pub fn norm(&self) -> f64 {
+        let [[a, b], [c, d]] = self.matrix;
+        // Largest singular value from the trace and determinant of MᵀM
+        let trace = a * a + b * b + c * c + d * d;
+        let det = self.determinant();
+        ((trace + (trace * trace - 4.0 * det * det).max(0.0).sqrt()) / 2.0).sqrt()
+    }
+
+    /// Point left in place, `None` when `I − matrix` is singular (never for
+    /// a contraction).
+    pub fn fixed_point(&self) -> Option<[f64; 2]> {
+        let [[a, b], [c, d]] = self.matrix;
+        let shift = Transform {
+            matrix: [[1.0 - a, -b], [-c, 1.0 - d]],
+            offset: [0.0, 0.0],
+            probability: None,
+        };
+        shift.inverse().map(|inverse| inverse.apply(self.offset))
+    }
+
+    /// Inverse map, `None` when the matrix is singular.
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.determinant();
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let [[a, b], [c, d]] = self.matrix;
+        let matrix = [[d / det, -b / det], [-c / det, a / det]];
+        let [x, y] = self.offset;
+        Some(Transform {
+            matrix,
+            offset: [-(matrix[0][0] * x + matrix[0][1] * y), -(matrix[1][0] * x + matrix[1][1] * y)],
+            probability: None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ifs {
+    #[serde(default)]
+    pub name: String,
+    pub maps: Vec<Transform>,
+}
+
+impl Ifs {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let ifs: Ifs = toml::from_str(&fs::read_to_string(path)?)?;
+        ifs.validate()?;
+        Ok(ifs)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        // Not pretty: the matrices stay on one line
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Entries stored in the exported images: the whole system, and the seed
+    /// of a chaos game.
+    pub fn metadata(&self, seed: Option<Seed>) -> Entries {
+        let mut entries = metadata::generator();
+        if let Ok(text) = toml::to_string(self) {
+            entries.push(entry(metadata::IFS, text));
+        }
+        if let Some(seed) = seed {
+            entries.push(entry(metadata::SEED, seed));
+        }
+        entries
+    }
+
+    /// Every map must contract, and the weights must be usable.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.maps.is_empty() {
+            return Err("le système n'a aucune application".to_string());
+        }
+        for (i, map) in self.maps.iter().enumerate() {
+            if map.matrix.iter().flatten().chain(&map.offset).any(|value| !value.is_finite()) {
+                return Err(format!("application {}: valeur non finie", i + 1));
+            }
+            if map.norm() >= 1.0 {
+                return Err(format!("application {}: pas contractante (norme {:.3})", i + 1, map.norm()));
+            }
+            if map.probability.is_some_and(|p| !(p >= 0.0 && p.is_finite())) {
+                return Err(format!("application {}: probabilité négative", i + 1));
+            }
+        }
+        if self.weights().iter().sum::<f64>() <= 0.0 {
+            return Err("les probabilités sont toutes nulles".to_string());
+        }
+        Ok(())
+    }
+
+    /// Cantor square of `rule`: one map of ratio `1/grid` per kept cell. Row
+    /// 0 of the rule is the top one, so it gets the highest `y`.
+    pub fn from_rule(rule: &Rule) -> Self {
+        let n = rule.grid as f64;
+        Self {
+            name: format!("Règle {}", rule),
+            maps: rule
+                .cells()
+                .map(|(x, y)| Transform::scaling(1.0 / n, [x as f64 / n, (n - 1.0 - y as f64) / n]))
+                .collect(),
+        }
+    }
+
+    /// Multi-scale construction, flipped like [`Ifs::from_rule`].
+    pub fn from_maps(maps: &Maps) -> Self {
+        Self {
+            name: format!("Homothéties {}", maps),
+            maps: maps
+                .0
+                .iter()
+                .map(|map| Transform::scaling(map.ratio, [map.offset[0], 1.0 - map.ratio - map.offset[1]]))
+                .collect(),
+        }
+    }
+
+    /// Chaos game weights: maps without a probability weigh their area ratio,
+    /// at least 1 % so that thin maps are still visited.
+    fn weights(&self) -> Vec<f64> {
+        self.maps
+            .iter()
+            .map(|map| map.probability.unwrap_or(map.determinant().abs().max(0.01)))
+            .collect()
+    }
+
+    /// Weights normalized to a sum of 1.
+    pub fn probabilities(&self) -> Vec<f64> {
+        let weights = self.weights();
+        let total: f64 = weights.iter().sum();
+        weights.iter().map(|weight| weight / total).collect()
+    }
+
+    /// Bounding box `[min_x, min_y, max_x, max_y]` of the attractor, from a
+    /// short chaos game and the fixed points of the maps, which the rare maps
+    /// of the walk may never reach (the stem of the fern). The walk can still
+    /// miss a few extremes, hence 1 % more on each side.
+    pub fn bounds(&self) -> [f64; 4] {
+        let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+        let mut add = |[x, y]: [f64; 2]| {
+            bounds = [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)];
+        };
+        self.maps.iter().filter_map(Transform::fixed_point).for_each(&mut add);
+        self.walk(&mut randomize::seeded(0), 20_000, add);
+        let pad = (bounds[2] - bounds[0]).max(bounds[3] - bounds[1]) * 0.01;
+        [bounds[0] - pad, bounds[1] - pad, bounds[2] + pad, bounds[3] + pad]
+    }
+
+    /// Chaos game of `points` steps from a random point, after a few steps to
+    /// reach the attractor.
+    fn walk(&self, rng: &mut impl Rng, points: u64, mut plot: impl FnMut([f64; 2])) {
+        let cumulative: Vec<f64> = self
+            .probabilities()
+            .iter()
+            .scan(0.0, |sum, p| {
+                *sum += p;
+                Some(*sum)
+            })
+            .collect();
+        let mut point = [rng.random::<f64>(), rng.random::<f64>()];
+        for step in 0..points + 20 {
+            let draw: f64 = rng.random();
+            let index = cumulative.iter().position(|&sum| draw < sum).unwrap_or(self.maps.len() - 1);
+            point = self.maps[index].apply(point);
+            if step >= 20 {
+                plot(point);
+            }
+        }
+    }
+
+    /// Density of `points` chaos game steps in a `width × height` view of the
+    /// attractor. The points are split in fixed chunks, each with its own
+    /// ChaCha8 stream of `seed`, so the result does not depend on the number
+    /// of threads.
+    pub fn chaos_game(&self, width: u32, height: u32, points: u64, seed: Seed) -> Histogram {
+        const CHUNK: u64 = 1 << 16;
+        let view = View::fit(self.bounds(), width, height);
+        let empty = || Histogram::new(width, height);
+
+        (0..points.div_ceil(CHUNK))
+            .into_par_iter()
+            .fold(empty, |mut histogram, chunk| {
+                let mut rng = randomize::seeded(seed);
+                rng.set_stream(chunk);
+                let count = CHUNK.min(points - chunk * CHUNK);
+                self.walk(&mut rng, count, |point| histogram.add(view.pixel(point)));
+                histogram
+            })
+            .reduce(empty, Histogram::merge)
+    }
+
+    /// Images of the unit square after `iterations` steps, like the recursive
+    /// drawing of the Cantor square: each composed map is filled, and a
+    /// branch stops once its image is narrower than a pixel, or every branch
+    /// one level earlier past [`MAX_SHAPES`] images.
+    pub fn deterministic(&self, width: u32, height: u32, iterations: u32) -> Histogram {
+        let view = View::fit(self.bounds(), width, height);
+        let shapes = self.compositions(iterations, view.scale);
+
+        let mut histogram = shapes
+            .par_iter()
+            .fold(
+                || Histogram::new(width, height),
+                |mut histogram, shape| {
+                    histogram.fill(&view, shape);
+                    histogram
+                },
+            )
+            .reduce(|| Histogram::new(width, height), Histogram::merge);
+        // Covered or not, like the squares of the Cantor drawings
+        histogram.counts.iter_mut().for_each(|count| *count = (*count).min(1));
+        histogram
+    }
+
+    /// Level by level rather than depth first, so that the budget stops all
+    /// the branches at the same depth.
+    fn compositions(&self, iterations: u32, scale: f64) -> Vec<Transform> {
+        let mut shapes = Vec::new();
+        let mut level = vec![Transform::scaling(1.0, [0.0, 0.0])];
+        for _ in 0..iterations {
+            let (small, large): (Vec<_>, Vec<_>) = level.into_iter().partition(|shape| shape.norm() * scale < 1.0);
+            shapes.extend(small);
+            level = large;
+            if level.is_empty() || shapes.len() + level.len() * self.maps.len() > MAX_SHAPES {
+                break;
+            }
+            level = level.iter().flat_map(|shape| self.maps.iter().map(|map| shape.compose(map))).collect();
+        }
+        shapes.extend(level);
+        shapes
+    }
+}
+
+/// Built-in systems of the `fractal ifs` command and the interface.
+pub fn gallery() -> Vec<Ifs> {
+    let map = |matrix, offset, probability| Transform {
+        matrix,
+        offset,
+        probability: Some(probability),
+    };
+    vec![
+        Ifs {
+            name: "Carré de Cantor".to_string(),
+            ..Ifs::from_rule(&Rule::cantor())
+        },
+        Ifs {
+            name: "Triangle de Sierpiński".to_string(),
+            maps: vec![
+                Transform::scaling(0.5, [0.0, 0.0]),
+                Transform::scaling(0.5, [0.5, 0.0]),
+                Transform::scaling(0.5, [0.25, 0.5]),
+            ],
+        },
+        Ifs {
+            name: "Fougère de Barnsley".to_string(),
+            maps: vec![
+                map([[0.0, 0.0], [0.0, 0.16]], [0.0, 0.0], 0.01),
+                map([[0.85, 0.04], [-0.04, 0.85]], [0.0, 1.6], 0.85),
+                map([[0.2, -0.26], [0.23, 0.22]], [0.0, 1.6], 0.07),
+                map([[-0.15, 0.28], [0.26, 0.24]], [0.0, 0.44], 0.07),
+            ],
+        },
+        Ifs {
+            name: "Dragon de Heighway".to_string(),
+            maps: vec![
+                map([[0.5, -0.5], [0.5, 0.5]], [0.0, 0.0], 0.5),
+                map([[-0.5, -0.5], [0.5, -0.5]], [1.0, 0.0], 0.5),
+            ],
+        },
+    ]
+}
+
+/// Command-line names of the [`gallery`] systems, in the same order.
+pub const GALLERY_KEYS: [&str; 4] = ["cantor", "sierpinski", "fougere", "dragon"];
+
+/// Gallery system named `key`, see [`GALLERY_KEYS`].
+pub fn named(key: &str) -> Result<Ifs, String> {
+    GALLERY_KEYS
+        .iter()
+        .position(|&name| name == key.trim().to_lowercase())
+        .map(|index| gallery().swap_remove(index))
+        .ok_or_else(|| format!("système inconnu: {} ({})", key, GALLERY_KEYS.join(", ")))
+}
+
+/// Rendering method of [`Ifs`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Random walk on the attractor, shaded by density.
+    #[default]
+    Chaos,
+    /// Images of the unit square, level by level.
+    Deterministic,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Chaos => "chaos",
+            Mode::Deterministic => "deterministe",
+        })
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "chaos" => Ok(Mode::Chaos),
+            "deterministe" | "déterministe" | "deterministic" => Ok(Mode::Deterministic),
+            _ => Err(format!("mode inconnu: {} (chaos, deterministe)", value)),
+        }
+    }
+}
+
+/// Square view fitting the attractor bounds with a small margin, `y` up.
+#[derive(Clone, Copy, Debug)]
+struct View {
+    min: [f64; 2],
+    max_y: f64,
+    /// Pixels per unit.
+    scale: f64,
+}
+
+impl View {
+    fn fit([min_x, min_y, max_x, max_y]: [f64; 4], width: u32, height: u32) -> Self {
+        let (w, h) = ((max_x - min_x).max(1e-9), (max_y - min_y).max(1e-9));
+        let scale = (width as f64 / w).min(height as f64 / h) * 0.95;
+        // Centered in the image
+        let margin = [(width as f64 / scale - w) / 2.0, (height as f64 / scale - h) / 2.0];
+        Self {
+            min: [min_x - margin[0], min_y - margin[1]],
+            max_y: max_y + margin[1],
+            scale,
+        }
+    }
+
+    fn pixel(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [(x - self.min[0]) * self.scale, (self.max_y - y) * self.scale]
+    }
+
+    fn world(&self, [px, py]: [f64; 2]) -> [f64; 2] {
+        [self.min[0] + px / self.scale, self.max_y - py / self.scale]
+    }
+}
+
+/// Hit counts per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub width: u32,
+    pub height: u32,
+    pub counts: Vec<u32>,
+}
+
+impl Histogram {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            counts: vec![0; width as usize * height as usize],
+        }
+    }
+
+    fn add(&mut self, [x, y]: [f64; 2]) {
+        if x >= 0.0 && y >= 0.0 && x < self.width as f64 && y < self.height as f64 {
+            let index = y as usize * self.width as usize + x as usize;
+            self.counts[index] = self.counts[index].saturating_add(1);
+        }
+    }
+
+    fn merge(mut self, other: Histogram) -> Histogram {
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count = count.saturating_add(other);
+        }
+        self
+    }
+
+    /// Counts the pixels whose center falls in the image of the unit square.
+    fn fill(&mut self, view: &View, shape: &Transform) {
+        let corners = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]].map(|corner| view.pixel(shape.apply(corner)));
+        let Some(inverse) = shape.inverse() else {
+            // Flat image: its corners only
+            corners.into_iter().for_each(|corner| self.add(corner));
+            return;
+        };
+        let range = |axis: usize, size: u32| {
+            let min = corners.iter().map(|c| c[axis]).fold(f64::INFINITY, f64::min).floor().max(0.0);
+            let max = corners.iter().map(|c| c[axis]).fold(f64::NEG_INFINITY, f64::max).ceil().min(size as f64);
+            min as u32..(max as u32).max(min as u32)
+        };
+        let (xs, ys) = (range(0, self.width), range(1, self.height));
+        let mut hit = false;
+        for py in ys.clone() {
+            for px in xs.clone() {
+                let [u, v] = inverse.apply(view.world([px as f64 + 0.5, py as f64 + 0.5]));
+                if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) {
+                    hit = true;
+                    self.add([px as f64, py as f64]);
+                }
+            }
+        }
+        // Thinner than a pixel: still visible at its center
+        if !hit {
+            self.add(view.pixel(shape.apply([0.5, 0.5])));
+        }
+    }
+
+    /// Density shaded from `background` to `fill`: `log(1 + count)` scaled by
+    /// the densest pixel, then raised to `1 / gamma`.
+    pub fn tone_map(&self, background: [u8; 4], fill: [u8; 4], gamma: f64) -> RgbaImage {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let (log_max, exponent) = ((1.0 + max).ln(), 1.0 / gamma.max(0.01));
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, &count) in image.pixels_mut().zip(&self.counts) {
+            let t = ((1.0 + count as f64).ln() / log_max).powf(exponent);
+            for channel in 0..4 {
+                let (from, to) = (background[channel] as f64, fill[channel] as f64);
+                pixel[channel] = (from + (to - from) * t).round() as u8;
+            }
+        }
+        image
+    }
+}
@@ -0,0 +1,247 @@
+//! One-dimensional Cantor sets on the unit interval, drawn as the stacked
+//! bars of the construction or as the graph of their Cantor function (the
+//! devil's staircase).
+//!
+//! The 1D rule is the top row of a 2D [`Rule`]: `3:101000101` gives the
+//! middle-thirds set.
+
+use crate::render::Style;
+use crate::rule::{self, Rule};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// What the still views draw.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Figure {
+    /// The 2D construction.
+    #[default]
+    Square,
+    /// The 1D set, one row of segments per iteration.
+    Bars,
+    /// Graph of the Cantor function of the 1D set.
+    Staircase,
+}
+
+impl Figure {
+    pub const ALL: [Figure; 3] = [Figure::Square, Figure::Bars, Figure::Staircase];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Figure::Square => "Carré",
+            Figure::Bars => "Ensemble 1D",
+            Figure::Staircase => "Escalier du diable",
+        }
+    }
+}
+
+/// Written like in project files: `square`, `bars` or `staircase`.
+impl fmt::Display for Figure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Figure::Square => "square",
+            Figure::Bars => "bars",
+            Figure::Staircase => "staircase",
+        })
+    }
+}
+
+impl FromStr for Figure {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "square" | "carré" => Ok(Figure::Square),
+            "bars" | "barres" => Ok(Figure::Bars),
+            "staircase" | "escalier" => Ok(Figure::Staircase),
+            _ => Err(format!("figure inconnue: {} (square, bars, staircase)", value)),
+        }
+    }
+}
+
+/// Segment of the construction, in units of the drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bar {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Rank of the kept cell chosen at each level, like [`Rule::squares`].
+    pub path: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub grid: u32,
+    /// Kept cells, left to right.
+    pub keep: Vec<bool>,
+}
+
+impl Interval {
+    /// Top row of `rule`.
+    pub fn from_rule(rule: &Rule) -> Self {
+        Self {
+            grid: rule.grid,
+            keep: (0..rule.grid).map(|x| rule.keeps(x, 0)).collect(),
+        }
+    }
+
+    pub fn kept(&self) -> u32 {
+        self.keep.iter().filter(|&&keep| keep).count() as u32
+    }
+
+    /// `log(kept) / log(grid)`: log 2 / log 3 ≈ 0.6309 for the middle thirds.
+    pub fn dimension(&self) -> f64 {
+        match self.kept() {
+            0 => 0.0,
+            kept => (kept as f64).ln() / (self.grid as f64).ln(),
+        }
+    }
+
+    /// Calls `visit(start, length, path)` for each segment of iteration
+    /// `iterations`, left to right.
+    pub fn segments(&self, iterations: u32, visit: &mut impl FnMut(f64, f64, &[u32])) {
+        let mut path = Vec::with_capacity(iterations as usize);
+        self.visit_segments(0.0, 1.0, iterations, &mut path, visit);
+    }
+
+    fn visit_segments(
+        &self,
+        start: f64,
+        length: f64,
+        iterations: u32,
+        path: &mut Vec<u32>,
+        visit: &mut impl FnMut(f64, f64, &[u32]),
+    ) {
+        if iterations == 0 {
+            visit(start, length, path);
+            return;
+        }
+        let sub = length / self.grid as f64;
+        let kept = self.keep.iter().enumerate().filter(|&(_, &keep)| keep);
+        for (rank, (index, _)) in kept.enumerate() {
+            path.push(rank as u32);
+            self.visit_segments(start + index as f64 * sub, sub, iterations - 1, path, visit);
+            path.pop();
+        }
+    }
+
+    /// Deepest iteration whose segments are still one pixel wide.
+    fn depth(&self, iterations: u32, size: u32) -> u32 {
+        iterations.min(rule::depth_limit(self.grid, size as f64 * 0.9))
+    }
+
+    /// Rows of the construction in a `size`-unit square, from the whole
+    /// interval at the top to iteration `iterations` at the bottom.
+    pub fn bars(&self, size: u32, iterations: u32) -> Vec<Bar> {
+        let size = size as f64;
+        let margin = size * 0.05;
+        let width = size - 2.0 * margin;
+        let rows = self.depth(iterations, size as u32) + 1;
+        let pitch = width / rows as f64;
+
+        let mut bars = Vec::new();
+        for level in 0..rows {
+            let y = margin + level as f64 * pitch + pitch * 0.2;
+            self.segments(level, &mut |start, length, path| {
+                bars.push(Bar {
+                    x: margin + start * width,
+                    y,
+                    width: length * width,
+                    height: pitch * 0.6,
+                    path: path.to_vec(),
+                });
+            });
+        }
+        bars
+    }
+
+    /// Vertices of the `iterations`-th approximation of the Cantor function in
+    /// a `size`-unit square, `y` down. It rises linearly on each segment, by
+    /// `kept^-iterations`, and is flat on the gaps.
+    pub fn staircase(&self, size: u32, iterations: u32) -> Vec<[f64; 2]> {
+        let kept = self.kept() as f64;
+        let size = size as f64;
+        let margin = size * 0.05;
+        let width = size - 2.0 * margin;
+        let iterations = self.depth(iterations, size as u32);
+        let point = |x: f64, y: f64| [margin + x * width, margin + (1.0 - y) * width];
+
+        let mut points = vec![point(0.0, 0.0)];
+        if kept == 0.0 {
+            points.push(point(1.0, 0.0));
+            return points;
+        }
+        let step = kept.powi(-(iterations as i32));
+        self.segments(iterations, &mut |start, length, path| {
+            // Value at the left end: the ranks are the digits in base `kept`
+            let value: f64 = path
+                .iter()
+                .enumerate()
+                .map(|(level, &rank)| rank as f64 * kept.powi(-(level as i32 + 1)))
+                .sum();
+            points.push(point(start, value));
+            points.push(point(start + length, value + step));
+        });
+        points.push(point(1.0, 1.0));
+        points.dedup();
+        points
+    }
+
+    /// Stacked bars on the background of `style`, colored like the squares.
+    pub fn render_bars(&self, size: u32, iterations: u32, style: &Style) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(size, size, Rgba(style.background));
+        for bar in self.bars(size, iterations) {
+            let color = Rgba(style.coloring.pick(&bar.path, style.fill, &style.palette));
+            // Rounded edges so that touching segments tile exactly
+            let (x0, x1) = (bar.x.round() as u32, ((bar.x + bar.width).round() as u32).max(bar.x.round() as u32 + 1));
+            let (y0, y1) = (bar.y.round() as u32, (bar.y + bar.height).round() as u32);
+            for py in y0..y1.min(size) {
+                for px in x0..x1.min(size) {
+                    image.put_pixel(px, py, color);
+                }
+            }
+        }
+        image
+    }
+
+    /// Devil's staircase in the fill color of `style`, inside a light frame.
+    pub fn render_staircase(&self, size: u32, iterations: u32, style: &Style) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(size, size, Rgba(style.background));
+        let frame = Rgba([200, 200, 200, 255]);
+        let (low, high) = (size as f64 * 0.05, size as f64 * 0.95);
+        for [from, to] in [
+            [[low, low], [high, low]],
+            [[high, low], [high, high]],
+            [[high, high], [low, high]],
+            [[low, high], [low, low]],
+        ] {
+            draw_line(&mut image, from, to, frame, 1);
+        }
+        let width = (size / 400).max(1) + 1;
+        for pair in self.staircase(size, iterations).windows(2) {
+            draw_line(&mut image, pair[0], pair[1], Rgba(style.fill), width);
+        }
+        image
+    }
+}
+
+/// Line of `width` pixels, stamped at every pixel step.
+fn draw_line(image: &mut RgbaImage, from: [f64; 2], to: [f64; 2], color: Rgba<u8>, width: u32) {
+    let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil().max(1.0) as u32;
+    let half = width as f64 / 2.0;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let (x, y) = (from[0] + (to[0] - from[0]) * t - half, from[1] + (to[1] - from[1]) * t - half);
+        for dy in 0..width {
+            for dx in 0..width {
+                let (px, py) = ((x + dx as f64).round(), (y + dy as f64).round());
+                if px >= 0.0 && py >= 0.0 && px < image.width() as f64 && py < image.height() as f64 {
+                    image.put_pixel(px as u32, py as u32, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Shared code for the FraCantor binaries.
+
+pub mod affine;
+pub mod animation;
+pub mod box_counting;
+pub mod camera;
+pub mod cli;
+pub mod construction;
+pub mod dust;
+pub mod format;
+pub mod history;
+pub mod ifs;
+pub mod interval;
+pub mod mesh;
+pub mod metadata;
+pub mod pipeline;
+pub mod preset;
+pub mod project;
+pub mod randomize;
+pub mod raycast;
+pub mod render;
+pub mod rule;
+pub mod server;
+pub mod solid;
+pub mod stats;
+pub mod tiles;
+pub mod timing;
+pub mod video;
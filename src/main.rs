@@ -1,150 +1,172 @@
-use image::{ImageBuffer, Rgba, RgbaImage};
+use fractal_generation_rust::animation::{self, AnimationOptions, FrameWriter, GifWriter};
+use fractal_generation_rust::camera::Easing;
+use fractal_generation_rust::construction::{self, Transition};
+use fractal_generation_rust::metadata::{self, entry};
+use fractal_generation_rust::pipeline;
+use fractal_generation_rust::rule::{self, Rule};
+use fractal_generation_rust::timing;
+use fractal_generation_rust::video;
+use image::{Rgba, RgbaImage};
 use std::io::{self, Write};
-use gif::{Frame, Encoder, Repeat};
-use std::fs::File;
-use std::borrow::Cow;
-
-fn draw_cantor_square(
-    image: &mut RgbaImage,
-    x: u32,
-    y: u32,
-    size: u32,
-    iterations: u32,
-    color: Rgba<u8>,
-) {
-    if size == 0 {
-        return;
-    }
-
-    if iterations == 0 {
-        draw_filled_rectangle(image, x, y, size, color);
-        return;
-    }
 
-    let sub_size = size / 3;
-
-    // Draw only 4 corner squares
-    for i in [0, 2].iter() {
-        for j in [0, 2].iter() {
-            draw_cantor_square(
-                image,
-                x + i * sub_size,
-                y + j * sub_size,
-                sub_size,
-                iterations - 1,
-                color,
-            );
-        }
-    }
-}
-
-fn draw_filled_rectangle(
-    image: &mut RgbaImage,
-    x: u32,
-    y: u32,
-    size: u32,
-    color: Rgba<u8>,
-) {
-    for i in 0..size {
-        for j in 0..size {
-            let px = x + i;
-            let py = y + j;
-            if px < image.width() && py < image.height() {
-                image.put_pixel(px, py, color);
-            }
-        }
-    }
+fn prompt(question: &str) -> String {
+    print!("{}", question);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().to_string()
 }
 
-fn rgba_to_indexed(image: &RgbaImage) -> Vec<u8> {
-    let mut indexed = Vec::new();
-    for pixel in image.pixels() {
-        // Simple conversion: if pixel is black, use index 1; otherwise index 0 (white)
-        if pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0 {
-            indexed.push(1);
-        } else {
-            indexed.push(0);
-        }
+fn main() {
+    println!("=== Générateur de Carré de Cantor (Animation) ===\n");
+
+    let size: u32 = prompt("Taille du carré initial (pixels) [par défaut: 486]: ")
+        .parse()
+        .unwrap_or(486);
+
+    let mut max_iterations: u32 = prompt("Nombre maximum d'itérations [par défaut: 4]: ")
+        .parse()
+        .unwrap_or(4);
+    let depth = rule::depth_limit(3, size as f64);
+    if max_iterations > depth {
+        println!("Limité à {} itérations : au-delà, les carrés font moins d'un pixel.", depth);
+        max_iterations = depth;
     }
-    indexed
-}
 
-fn main() {
-    println!("=== Générateur de Carré de Cantor (GIF Animé) ===\n");
+    // Frame delay in milliseconds, also accepted as a frame rate ("2fps")
+    let frame_delay = timing::parse_delay(&prompt("Délai entre les frames (ms ou fps) [par défaut: 500]: "))
+        .unwrap_or(500.0);
+
+    let hold_last = timing::parse_delay(&prompt("Pause sur la dernière itération en ms [par défaut: 0]: "))
+        .unwrap_or(0.0);
+
+    let tweens: u32 = prompt("Frames de transition entre deux itérations [par défaut: 0]: ")
+        .parse()
+        .unwrap_or(0);
+
+    let (transition, easing, tween_delay) = if tweens > 0 {
+        let transition: Transition = prompt("Transition (fondu/retrait) [par défaut: fondu]: ")
+            .parse()
+            .unwrap_or_default();
+        let easing: Easing = prompt("Courbe (linear/ease-in-out/exponential) [par défaut: ease-in-out]: ")
+            .parse()
+            .unwrap_or(Easing::EaseInOut);
+        let tween_delay = timing::parse_delay(&prompt("Délai des frames de transition (ms ou fps) [par défaut: 40]: "))
+            .unwrap_or(40.0);
+        (transition, easing, tween_delay)
+    } else {
+        (Transition::default(), Easing::Linear, frame_delay)
+    };
 
-    // Get initial size
-    print!("Taille du carré initial (pixels) [par défaut: 486]: ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let size: u32 = input.trim().parse().unwrap_or(486);
+    let ping_pong = matches!(
+        prompt("Boucle aller-retour (o/n) [par défaut: n]: ").as_str(),
+        "o" | "oui" | "y"
+    );
 
-    // Get max iterations
-    print!("Nombre maximum d'itérations [par défaut: 4]: ");
-    io::stdout().flush().unwrap();
-    input.clear();
-    io::stdin().read_line(&mut input).unwrap();
-    let max_iterations: u32 = input.trim().parse().unwrap_or(4);
+    let plays: u32 = prompt("Nombre de lectures, 0 = en boucle [par défaut: 0]: ")
+        .parse()
+        .unwrap_or(0);
 
-    // Get frame delay (milliseconds)
-    print!("Délai entre les frames en ms [par défaut: 500]: ");
-    io::stdout().flush().unwrap();
-    input.clear();
-    io::stdin().read_line(&mut input).unwrap();
-    let frame_delay: u16 = input.trim().parse().unwrap_or(500);
+    let transparent = matches!(
+        prompt("Fond transparent (o/n) [par défaut: n]: ").as_str(),
+        "o" | "oui" | "y"
+    );
 
-    // Get output filename
-    print!("Nom du fichier GIF [par défaut: cantor_animation.gif]: ");
-    io::stdout().flush().unwrap();
-    input.clear();
-    io::stdin().read_line(&mut input).unwrap();
-    let filename = match input.trim() {
+    let filename = match prompt("Nom du fichier (.gif, .png, .webp, .mp4, .webm) [par défaut: cantor_animation.gif]: ").as_str() {
         "" => "cantor_animation.gif".to_string(),
         name => name.to_string(),
     };
+    let extension = filename.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+
+    let mut metadata = metadata::generator();
+    metadata.extend([
+        entry(metadata::RULE, Rule::cantor()),
+        entry(metadata::ITERATIONS, max_iterations),
+        entry(metadata::PALETTE, "#000000"),
+    ]);
+    let mut options = AnimationOptions {
+        plays,
+        metadata,
+        ..AnimationOptions::default()
+    };
+    if extension == "mp4" || extension == "webm" {
+        options.video.fps = prompt("Images par seconde [par défaut: 25]: ")
+            .parse()
+            .unwrap_or(options.video.fps)
+            .max(1);
+        options.video.quality = prompt("Qualité CRF, plus bas = meilleur [par défaut: 23]: ")
+            .parse()
+            .unwrap_or(options.video.quality);
+    }
 
-    // Color palette: white (0,0,0) and black (1,0,0)
-    let color_map = vec![255, 255, 255, 0, 0, 0];
-
-    println!("\nGénération de l'animation GIF...");
-
-    // Create GIF encoder
-    let output = File::create(&filename)
-        .expect("Erreur: impossible de créer le fichier GIF");
-    let mut encoder = Encoder::new(output, size as u16, size as u16, &color_map)
-        .expect("Erreur: impossible de créer l'encodeur GIF");
-    
-    encoder.set_repeat(Repeat::Infinite)
-        .expect("Erreur: impossible de définir la boucle infinie");
-
-    // Generate frames for each iteration
-    for current_iter in 0..=max_iterations {
-        // Create a new image for this iteration
-        let mut image = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
-        let color = Rgba([0, 0, 0, 255]);
-
-        // Draw the Cantor square at this iteration level
-        draw_cantor_square(&mut image, 0, 0, size, current_iter, color);
-
-        // Convert RGBA to indexed color (black/white only)
-        let indexed = rgba_to_indexed(&image);
+    println!("\nGénération de l'animation...");
 
-        // Create frame
-        let mut frame = Frame::default();
-        frame.width = size as u16;
-        frame.height = size as u16;
-        frame.delay = frame_delay / 10; // GIF delay is in units of 10ms
-        frame.buffer = Cow::Borrowed(&indexed);
+    let steps = construction::schedule(max_iterations, tweens, easing, ping_pong);
+    let background = if transparent {
+        Rgba([255, 255, 255, 0])
+    } else {
+        Rgba([255, 255, 255, 255])
+    };
+    let color = Rgba([0, 0, 0, 255]);
+
+    // Grayscale palette for opaque GIF so that faded bands keep their intermediate tones
+    let mut writer: Box<dyn FrameWriter> = if extension == "gif" && !transparent {
+        let mut writer = GifWriter::create(&filename, size, size, plays)
+            .expect("Erreur: impossible de créer le fichier GIF")
+            .grayscale();
+        writer
+            .comment(&options.metadata)
+            .expect("Erreur: impossible d'écrire les métadonnées GIF");
+        Box::new(writer)
+    } else {
+        if let Some(notice) = video::fallback_notice(&filename) {
+            println!("{}", notice);
+        }
+        animation::create(&filename, size, size, steps.len() as u32, &options)
+            .expect("Erreur: impossible de créer le fichier d'animation")
+    };
 
-        encoder.write_frame(&frame)
-            .expect("Erreur: impossible d'écrire la frame GIF");
+    // Frames are drawn in parallel while the previous ones are encoded
+    pipeline::stream(
+        steps.len() as u32,
+        pipeline::default_lookahead(),
+        |index| {
+            // Create a new image for this frame
+            let mut image = RgbaImage::from_pixel(size, size, background);
+
+            // Draw the Cantor square at this iteration level, or between two levels
+            construction::draw_step(&mut image, size, steps[index as usize], transition, color, background);
+            image
+        },
+        |index, image| {
+            let step = steps[index as usize];
+            let delay = match step {
+                _ if step.is_tween() => tween_delay,
+                // Hold the complete construction longer
+                _ if step.level == max_iterations => frame_delay + hold_last,
+                _ => frame_delay,
+            };
+            writer.write_frame(&image, delay)?;
+
+            if !step.is_tween() {
+                println!("✓ Itération {} générée", step.level);
+            }
+            Ok(())
+        },
+    )
+    .expect("Erreur: impossible d'écrire la frame");
 
-        println!("✓ Itération {} générée", current_iter);
-    }
+    writer.finish().expect("Erreur: impossible de finaliser l'animation");
 
-    println!("\n✓ GIF animé sauvegardé: {}", filename);
+    println!("\n✓ Animation sauvegardée: {}", filename);
     println!("  - Taille: {} × {} pixels", size, size);
     println!("  - Itérations: 0 à {}", max_iterations);
     println!("  - Délai par frame: {}ms", frame_delay);
-    println!("  - Nombre total de frames: {}", max_iterations + 1);
-}
\ No newline at end of file
+    if hold_last > 0.0 {
+        println!("  - Pause finale: {}ms", hold_last);
+    }
+    if tweens > 0 {
+        println!("  - Transitions: {} frames de {}ms", tweens, tween_delay);
+    }
+    println!("  - Nombre total de frames: {}", steps.len());
+}
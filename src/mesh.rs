@@ -0,0 +1,154 @@
+//! Triangle meshes of voxel solids, written as STL, OBJ or PLY.
+//!
+//! Only the faces between an occupied and an empty cell are kept: the faces
+//! shared by two cubes are inside the solid and would only bloat the file and
+//! break slicers. Vertices are shared between faces.
+
+use crate::solid::Voxels;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Directions of the 6 faces of a cube, and their corners in the order that
+/// makes them counter-clockwise seen from outside.
+const FACES: [([i64; 3], [[u32; 3]; 4]); 6] = [
+    ([-1, 0, 0], [[0, 0, 0], [0, 0, 1], [0, 1, 1], [0, 1, 0]]),
+    ([1, 0, 0], [[1, 0, 0], [1, 1, 0], [1, 1, 1], [1, 0, 1]]),
+    ([0, -1, 0], [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]]),
+    ([0, 1, 0], [[0, 1, 0], [0, 1, 1], [1, 1, 1], [1, 1, 0]]),
+    ([0, 0, -1], [[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 0, 0]]),
+    ([0, 0, 1], [[0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]]),
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    /// Counter-clockwise seen from outside.
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Counts of [`Mesh::from_voxels`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    pub cubes: u64,
+    /// Faces shared by two cubes, left out.
+    pub internal_faces: u64,
+}
+
+impl Mesh {
+    /// Surface of the occupied cells, scaled so the whole grid is a cube of
+    /// side `size`.
+    pub fn from_voxels(voxels: &Voxels, size: f32) -> (Self, MeshStats) {
+        let scale = size / voxels.resolution.max(1) as f32;
+        let mut mesh = Mesh::default();
+        let mut indices: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut stats = MeshStats {
+            cubes: voxels.len(),
+            internal_faces: 0,
+        };
+
+        for cell in voxels.iter() {
+            for (direction, corners) in &FACES {
+                let neighbour = [0, 1, 2].map(|axis| cell[axis] as i64 + direction[axis]);
+                if voxels.contains(neighbour) {
+                    stats.internal_faces += 1;
+                    continue;
+                }
+                let quad = corners.map(|corner| {
+                    let point = [0, 1, 2].map(|axis| cell[axis] + corner[axis]);
+                    *indices.entry(point).or_insert_with(|| {
+                        mesh.vertices.push(point.map(|c| c as f32 * scale));
+                        mesh.vertices.len() as u32 - 1
+                    })
+                });
+                mesh.triangles.push([quad[0], quad[1], quad[2]]);
+                mesh.triangles.push([quad[0], quad[2], quad[3]]);
+            }
+        }
+        // Each shared face was seen from both cubes
+        stats.internal_faces /= 2;
+        (mesh, stats)
+    }
+
+    fn normal(&self, [a, b, c]: [u32; 3]) -> [f32; 3] {
+        let [a, b, c] = [a, b, c].map(|index| self.vertices[index as usize]);
+        let (u, v) = ([0, 1, 2].map(|i| b[i] - a[i]), [0, 1, 2].map(|i| c[i] - a[i]));
+        let n = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt().max(f32::MIN_POSITIVE);
+        n.map(|c| c / length)
+    }
+
+    /// Writes the mesh in the format given by the extension of `path`.
+    pub fn save(&self, path: impl AsRef<Path>, name: &str) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let mut file = BufWriter::new(File::create(path)?);
+        match extension.as_deref() {
+            Some("stl") => self.write_stl(&mut file, name)?,
+            Some("obj") => self.write_obj(&mut file, name)?,
+            Some("ply") => self.write_ply(&mut file, name)?,
+            _ => return Err(format!("format de maillage non reconnu: {} (stl, obj, ply)", path.display()).into()),
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Binary STL: 80-byte header, then each triangle with its normal.
+    pub fn write_stl(&self, out: &mut impl Write, name: &str) -> std::io::Result<()> {
+        let mut header = [b' '; 80];
+        let name = format!("FraCantor {}", name);
+        let len = name.len().min(80);
+        header[..len].copy_from_slice(&name.as_bytes()[..len]);
+        out.write_all(&header)?;
+        out.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+        for &triangle in &self.triangles {
+            let corners = triangle.map(|index| self.vertices[index as usize]);
+            for value in std::iter::once(self.normal(triangle)).chain(corners).flatten() {
+                out.write_all(&value.to_le_bytes())?;
+            }
+            out.write_all(&[0, 0])?;
+        }
+        Ok(())
+    }
+
+    /// Wavefront OBJ, indices from 1.
+    pub fn write_obj(&self, out: &mut impl Write, name: &str) -> std::io::Result<()> {
+        writeln!(out, "# FraCantor")?;
+        writeln!(out, "o {}", name.replace(char::is_whitespace, "_"))?;
+        for [x, y, z] in &self.vertices {
+            writeln!(out, "v {} {} {}", x, y, z)?;
+        }
+        for [a, b, c] in &self.triangles {
+            writeln!(out, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Binary little-endian PLY.
+    pub fn write_ply(&self, out: &mut impl Write, name: &str) -> std::io::Result<()> {
+        write!(
+            out,
+            "ply\nformat binary_little_endian 1.0\ncomment FraCantor {}\n\
+             element vertex {}\nproperty float x\nproperty float y\nproperty float z\n\
+             element face {}\nproperty list uchar int vertex_indices\nend_header\n",
+            name.replace('\n', " "),
+            self.vertices.len(),
+            self.triangles.len()
+        )?;
+        for value in self.vertices.iter().flatten() {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        for triangle in &self.triangles {
+            out.write_all(&[3])?;
+            for index in triangle {
+                out.write_all(&(*index as i32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! Render parameters written into exported files and read back from them.
+//!
+//! PNG and APNG get one text chunk per entry. SVG, GIF and JPEG get all the
+//! entries in a single TOML text: in a `<metadata>` element, a comment
+//! extension and `COM` segments.
+
+use image::codecs::jpeg::JpegEncoder;
+use crate::format::{self, ImageOptions};
+use image::{ColorType, RgbaImage};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::Path;
+use svg::Node;
+use svg::node::Text;
+use svg::node::element::Element;
+
+/// Name of the program that made the file.
+pub const SOFTWARE: &str = "Software";
+pub const VERSION: &str = "fracantor:version";
+/// Rule in its `grid:mask` form.
+pub const RULE: &str = "fracantor:rule";
+pub const ITERATIONS: &str = "fracantor:iterations";
+/// Palette as `#rrggbb` colors separated by commas.
+pub const PALETTE: &str = "fracantor:palette";
+/// Index of the fill color in the palette.
+pub const COLOR: &str = "fracantor:color";
+pub const COLORING: &str = "fracantor:coloring";
+/// Random dust selection (`p=0.6`, `k=4`), `aucun` for the rule mask.
+pub const DUST: &str = "fracantor:dust";
+/// Multi-scale maps (`0.5@0,0;0.25@0.75,0`), `aucune` for the rule.
+pub const MAPS: &str = "fracantor:maps";
+/// Still figure: `square`, `bars` or `staircase`.
+pub const FIGURE: &str = "fracantor:figure";
+pub const ZOOM: &str = "fracantor:zoom";
+/// Zoom target as `x,y`.
+pub const TARGET: &str = "fracantor:target";
+/// Seed of the random draws, see [`crate::randomize::seeded`].
+pub const SEED: &str = "fracantor:seed";
+/// 3D rule of `fractal mesh` and `fractal preview`, see [`crate::solid::SolidRule`].
+pub const SOLID: &str = "fracantor:solid";
+/// Iterated function system of `fractal ifs`, as TOML.
+pub const IFS: &str = "fracantor:ifs";
+/// Whole project file, when the export comes from a project.
+pub const PROJECT: &str = "fracantor:project";
+
+/// `(key, value)` pairs, in the order they are written.
+pub type Entries = Vec<(String, String)>;
+
+/// Generator name and version, the first entries of every export.
+pub fn generator() -> Entries {
+    vec![entry(SOFTWARE, "FraCantor"), entry(VERSION, env!("CARGO_PKG_VERSION"))]
+}
+
+pub fn entry(key: &str, value: impl ToString) -> (String, String) {
+    (key.to_string(), value.to_string())
+}
+
+/// Value of `key`, if present.
+pub fn get<'a>(entries: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    entries
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Entries as a TOML table of strings, for the formats with a single text.
+/// The order is kept both ways (toml's `preserve_order`).
+pub fn to_text(entries: &[(String, String)]) -> String {
+    let table: toml::Table = entries
+        .iter()
+        .map(|(key, value)| (key.clone(), toml::Value::String(value.clone())))
+        .collect();
+    table.to_string()
+}
+
+pub fn from_text(text: &str) -> Result<Entries, Box<dyn Error>> {
+    let table: toml::Table = text.parse()?;
+    table
+        .into_iter()
+        .map(|(key, value)| match value {
+            toml::Value::String(value) => Ok((key, value)),
+            value => Err(format!("métadonnée {} invalide: {}", key, value).into()),
+        })
+        .collect()
+}
+
+/// SVG `<metadata>` element holding the entries.
+pub fn svg_element(entries: &[(String, String)]) -> Element {
+    let mut element = Element::new("metadata");
+    element.append(Text::new(format!("\n{}", to_text(entries))));
+    element
+}
+
+pub(crate) fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Saves `image` with the default [`ImageOptions`], see [`format::save`].
+pub fn save_image(
+    image: &RgbaImage,
+    path: impl AsRef<Path>,
+    entries: &[(String, String)],
+) -> Result<(), Box<dyn Error>> {
+    format::save(image, path, entries, &ImageOptions::default())
+}
+
+/// Adds the entries to a PNG encoder: `tEXt` for ASCII values, `iTXt` (UTF-8)
+/// otherwise.
+pub fn add_png_text<W: std::io::Write>(
+    encoder: &mut png::Encoder<W>,
+    entries: &[(String, String)],
+) -> Result<(), Box<dyn Error>> {
+    for (key, value) in entries {
+        if value.is_ascii() {
+            encoder.add_text_chunk(key.clone(), value.clone())?;
+        } else {
+            encoder.add_itxt_chunk(key.clone(), value.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// PNG of 8-bit RGB or RGBA `samples` with the entries in text chunks,
+/// widened to 16 bits per channel if asked.
+pub(crate) fn write_png(
+    out: &mut impl Write,
+    samples: &[u8],
+    color: ColorType,
+    width: u32,
+    height: u32,
+    entries: &[(String, String)],
+    options: &ImageOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(if color == ColorType::Rgba8 { png::ColorType::Rgba } else { png::ColorType::Rgb });
+    encoder.set_compression(options.png_compression.png());
+    add_png_text(&mut encoder, entries)?;
+    if options.sixteen_bit {
+        encoder.set_depth(png::BitDepth::Sixteen);
+        // PNG samples are big-endian
+        let wide: Vec<u8> = samples.iter().flat_map(|&c| (c as u16 * 257).to_be_bytes()).collect();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&wide)?;
+        writer.finish()?;
+    } else {
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(samples)?;
+        writer.finish()?;
+    }
+    Ok(())
+}
+
+/// JPEG of RGB `samples` with the entries in `COM` segments placed after the
+/// `APPn` segments, JFIF requiring `APP0` right after `SOI`; long texts are
+/// split over several segments.
+pub(crate) fn write_jpeg(
+    out: &mut impl Write,
+    samples: &[u8],
+    width: u32,
+    height: u32,
+    entries: &[(String, String)],
+    quality: u8,
+) -> Result<(), Box<dyn Error>> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, quality).encode(samples, width, height, ColorType::Rgb8)?;
+
+    // End of SOI and of the APP0..APP15 segments that follow it
+    let mut split = 2;
+    while let [0xFF, 0xE0..=0xEF, high, low, ..] = jpeg[split.min(jpeg.len())..] {
+        split += 2 + u16::from_be_bytes([high, low]) as usize;
+    }
+    let split = split.min(jpeg.len());
+
+    let text = to_text(entries);
+    out.write_all(&jpeg[..split])?;
+    // The segment length counts its own 2 bytes
+    for chunk in text.as_bytes().chunks(u16::MAX as usize - 2) {
+        out.write_all(&[0xFF, 0xFE])?;
+        out.write_all(&(chunk.len() as u16 + 2).to_be_bytes())?;
+        out.write_all(chunk)?;
+    }
+    out.write_all(&jpeg[split..])?;
+    Ok(())
+}
+
+/// Reads back the entries written in a PNG, APNG, SVG, GIF or JPEG file.
+pub fn read(path: impl AsRef<Path>) -> Result<Entries, Box<dyn Error>> {
+    let path = path.as_ref();
+    let entries = match extension(path).as_deref() {
+        Some("png") | Some("apng") => read_png(path)?,
+        Some("svg") => read_svg(&fs::read_to_string(path)?)?,
+        Some("gif") => from_text(&read_gif_comments(&fs::read(path)?)?)?,
+        Some("jpg") | Some("jpeg") => from_text(&read_jpeg_comments(&fs::read(path)?)?)?,
+        _ => return Err(format!("format non reconnu: {} (png, svg, gif, jpg)", path.display()).into()),
+    };
+    if get(&entries, SOFTWARE) != Some("FraCantor") {
+        return Err(format!("aucune métadonnée FraCantor dans {}", path.display()).into());
+    }
+    Ok(entries)
+}
+
+fn read_png(path: &Path) -> Result<Entries, Box<dyn Error>> {
+    let reader = png::Decoder::new(BufReader::new(File::open(path)?)).read_info()?;
+    let info = reader.info();
+    let mut entries: Entries = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+    for chunk in &info.utf8_text {
+        entries.push((chunk.keyword.clone(), chunk.get_text()?));
+    }
+    Ok(entries)
+}
+
+fn read_svg(text: &str) -> Result<Entries, Box<dyn Error>> {
+    let start = text.find("<metadata>").ok_or("pas d'élément <metadata>")? + "<metadata>".len();
+    let end = start + text[start..].find("</metadata>").ok_or("<metadata> non fermé")?;
+    let content = text[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    from_text(&content)
+}
+
+/// Concatenated comment extensions of a GIF.
+fn read_gif_comments(bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+    let truncated = || "GIF tronqué";
+    let byte = |pos: usize| bytes.get(pos).copied().ok_or_else(truncated);
+    // Data sub-blocks, each prefixed by its length, up to an empty block
+    let sub_blocks = |mut pos: usize, out: &mut Vec<u8>| -> Result<usize, Box<dyn Error>> {
+        loop {
+            let len = byte(pos)? as usize;
+            pos += 1;
+            if len == 0 {
+                return Ok(pos);
+            }
+            out.extend_from_slice(bytes.get(pos..pos + len).ok_or_else(truncated)?);
+            pos += len;
+        }
+    };
+    // Color table of 2^(n+1) RGB entries when the high bit of `flags` is set
+    let color_table = |flags: u8| if flags & 0x80 != 0 { 3 << ((flags & 0x07) + 1) } else { 0 };
+
+    if !bytes.starts_with(b"GIF") {
+        return Err("fichier GIF invalide".into());
+    }
+    let mut pos = 13 + color_table(byte(10)?);
+    let (mut comments, mut skipped) = (Vec::new(), Vec::new());
+    loop {
+        match byte(pos)? {
+            0x21 => {
+                let out = if byte(pos + 1)? == 0xFE { &mut comments } else { &mut skipped };
+                pos = sub_blocks(pos + 2, out)?;
+            }
+            0x2C => {
+                pos += 10 + color_table(byte(pos + 9)?);
+                // LZW minimum code size, then the image data
+                pos = sub_blocks(pos + 1, &mut skipped)?;
+                skipped.clear();
+            }
+            0x3B => break,
+            block => return Err(format!("bloc GIF inconnu: {:#x}", block).into()),
+        }
+    }
+    Ok(String::from_utf8(comments)?)
+}
+
+/// Concatenated `COM` segments of a JPEG, read up to the image data.
+fn read_jpeg_comments(bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Err("fichier JPEG invalide".into());
+    }
+    let mut comments = Vec::new();
+    let mut pos = 2;
+    while let [0xFF, marker, high, low, ..] = bytes[pos.min(bytes.len())..] {
+        // Start of scan: no more segments before the image data
+        if marker == 0xDA {
+            break;
+        }
+        let len = u16::from_be_bytes([high, low]) as usize;
+        let segment = bytes.get(pos + 4..pos + 2 + len).ok_or("JPEG tronqué")?;
+        if marker == 0xFE {
+            comments.extend_from_slice(segment);
+        }
+        pos += 2 + len;
+    }
+    Ok(String::from_utf8(comments)?)
+}
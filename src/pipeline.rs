@@ -0,0 +1,53 @@
+//! Streaming frame pipeline: frames are rendered in parallel ahead of a
+//! single-threaded consumer (usually the encoder), through a bounded channel.
+//!
+//! At most `2 × lookahead` frames are alive at any time, whatever the length
+//! of the animation.
+
+use image::RgbaImage;
+use rayon::prelude::*;
+use std::error::Error;
+use std::sync::mpsc;
+use std::thread;
+
+/// One frame in flight per rayon worker.
+pub fn default_lookahead() -> usize {
+    rayon::current_num_threads()
+}
+
+/// Renders frames `0..count` with `render` and hands them to `consume` in order.
+///
+/// Frames are rendered `lookahead` at a time on the rayon pool while the
+/// calling thread consumes the previous batch. An error from `consume` stops
+/// the rendering.
+pub fn stream<R, C>(count: u32, lookahead: usize, render: R, mut consume: C) -> Result<(), Box<dyn Error>>
+where
+    R: Fn(u32) -> RgbaImage + Sync,
+    C: FnMut(u32, RgbaImage) -> Result<(), Box<dyn Error>>,
+{
+    let lookahead = lookahead.max(1);
+    // The batch being rendered, the channel and the frame held by the
+    // consumer: lookahead + (lookahead - 1) + 1 frames at most
+    let (sender, receiver) = mpsc::sync_channel(lookahead - 1);
+    let render = &render;
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for start in (0..count).step_by(lookahead) {
+                let end = count.min(start + lookahead as u32);
+                let frames: Vec<RgbaImage> = (start..end).into_par_iter().map(render).collect();
+                for (index, frame) in (start..end).zip(frames) {
+                    // The consumer hung up after an error
+                    if sender.send((index, frame)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        for (index, frame) in receiver {
+            consume(index, frame)?;
+        }
+        Ok(())
+    })
+}
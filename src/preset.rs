@@ -0,0 +1,157 @@
+//! Named presets: the look of a project (rule, iterations, colors, view),
+//! without its export settings.
+
+use crate::affine::Maps;
+use crate::dust::Selection;
+use crate::interval::Figure;
+use crate::project::Project;
+use crate::render::Coloring;
+use crate::rule::Rule;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    pub name: String,
+    pub rule: Rule,
+    pub iterations: u32,
+    pub zoom: f32,
+    #[serde(default)]
+    pub target: [f32; 2],
+    pub selected_color: usize,
+    pub palette: Vec<[u8; 4]>,
+    #[serde(default)]
+    pub coloring: Coloring,
+    /// Random dust, drawn with the seed of the project it is applied to.
+    #[serde(default)]
+    pub dust: Option<Selection>,
+    /// Multi-scale maps replacing the rule.
+    #[serde(default, skip_serializing_if = "Maps::is_empty")]
+    pub maps: Maps,
+    #[serde(default)]
+    pub figure: Figure,
+}
+
+/// File of presets, as exported and imported from the interface.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PresetFile {
+    #[serde(default)]
+    presets: Vec<Preset>,
+}
+
+impl Preset {
+    /// Captures the look of `project`.
+    pub fn from_project(name: impl Into<String>, project: &Project) -> Self {
+        Self {
+            name: name.into(),
+            rule: project.rule.clone(),
+            iterations: project.iterations,
+            zoom: project.zoom,
+            target: project.target,
+            selected_color: project.selected_color,
+            palette: project.palette.clone(),
+            coloring: project.coloring,
+            dust: project.dust,
+            maps: project.maps.clone(),
+            figure: project.figure,
+        }
+    }
+
+    /// Applies the preset to `project`, keeping its camera and export settings.
+    pub fn apply(&self, project: &mut Project) {
+        project.rule = self.rule.clone();
+        project.iterations = self.iterations;
+        project.zoom = self.zoom;
+        project.target = self.target;
+        project.palette = self.palette.clone();
+        project.selected_color = self.selected_color.min(self.palette.len().saturating_sub(1));
+        project.coloring = self.coloring;
+        project.dust = self.dust;
+        project.maps = self.maps.clone();
+        project.figure = self.figure;
+    }
+
+    /// Checks the preset against the same rules as a project.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let mut project = Project::default();
+        self.apply(&mut project);
+        project.selected_color = self.selected_color;
+        project
+            .validate()
+            .map_err(|err| format!("preset « {} »: {}", self.name, err).into())
+    }
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Preset>, Box<dyn Error>> {
+    let file: PresetFile = toml::from_str(&fs::read_to_string(path)?)?;
+    for preset in &file.presets {
+        preset.validate()?;
+    }
+    Ok(file.presets)
+}
+
+pub fn save(path: impl AsRef<Path>, presets: &[Preset]) -> Result<(), Box<dyn Error>> {
+    let file = PresetFile {
+        presets: presets.to_vec(),
+    };
+    fs::write(path, toml::to_string_pretty(&file)?)?;
+    Ok(())
+}
+
+/// Built-in gallery of variants, shown before the user's presets.
+pub fn gallery() -> Vec<Preset> {
+    let palette = Project::default().palette;
+    let preset = |name: &str, rule: &str, iterations, selected_color, coloring| Preset {
+        name: name.to_string(),
+        rule: rule.parse().expect("règle de la galerie invalide"),
+        iterations,
+        zoom: 1.0,
+        target: [0.0, 0.0],
+        selected_color,
+        palette: palette.clone(),
+        coloring,
+        dust: None,
+        maps: Maps::default(),
+        figure: Figure::Square,
+    };
+
+    vec![
+        preset("Poussière de Cantor", "3:101000101", 4, 0, Coloring::Uniform),
+        preset("Tapis de Sierpiński", "3:111101111", 4, 3, Coloring::Uniform),
+        preset("Croix de Vicsek", "3:010111010", 4, 2, Coloring::Uniform),
+        preset("Sautoir", "3:101010101", 4, 4, Coloring::Branch),
+        preset("Poussière 4×4", "4:1001000000001001", 3, 1, Coloring::Uniform),
+        preset("Dentelle 5×5", "5:1010100000101000000010101", 3, 3, Coloring::Mosaic),
+        preset("Quadrants", "3:101000101", 5, 0, Coloring::Branch),
+        preset("Mosaïque", "3:111101111", 4, 0, Coloring::Mosaic),
+        Preset {
+            zoom: 9.0,
+            target: [0.5, 0.5],
+            ..preset("Cœur du tapis", "3:111101111", 5, 4, Coloring::Mosaic)
+        },
+        Preset {
+            dust: Some(Selection::Pick(4)),
+            ..preset("Poussière aléatoire", "3:101000101", 5, 3, Coloring::Uniform)
+        },
+        Preset {
+            dust: Some(Selection::Probability(0.6)),
+            ..preset("Percolation", "3:111111111", 5, 2, Coloring::Branch)
+        },
+        Preset {
+            maps: Maps::example(),
+            ..preset("Cantor multi-échelle", "3:101000101", 6, 1, Coloring::Branch)
+        },
+        Preset {
+            figure: Figure::Bars,
+            ..preset("Ensemble de Cantor", "3:101000101", 6, 3, Coloring::Branch)
+        },
+        Preset {
+            figure: Figure::Staircase,
+            ..preset("Escalier du diable", "3:101000101", 8, 4, Coloring::Uniform)
+        },
+    ]
+}
@@ -0,0 +1,477 @@
+//! Project files: the settings of a FraCantor session, saved as TOML so the
+//! same look can be rendered again from the command line.
+
+use crate::affine::Maps;
+use crate::animation::{self, AnimationOptions};
+use crate::cli;
+use crate::dust::{Dust, Selection};
+use crate::format::{self, ImageFormat, ImageOptions};
+use crate::interval::{Figure, Interval};
+use crate::metadata::{self, Entries, entry};
+use crate::camera::{Camera, CameraPath, Easing, Keyframe};
+use crate::pipeline;
+use crate::randomize::Seed;
+use crate::render::{self, Coloring, Style};
+use crate::rule::{self, Rule};
+use crate::timing::Timing;
+use crate::video::VideoOptions;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use svg::Document;
+use svg::node::element::{Polyline, Rectangle};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Project {
+    pub rule: Rule,
+    pub iterations: u32,
+    /// Zoom of the still view.
+    pub zoom: f32,
+    /// Point of the unit square that stays in place when zooming the still
+    /// view: `[0, 0]` zooms towards the top-left corner.
+    pub target: [f32; 2],
+    /// Index of the fill color in `palette`.
+    pub selected_color: usize,
+    pub palette: Vec<[u8; 4]>,
+    pub coloring: Coloring,
+    /// Random dust: the cells kept in each square are drawn with `seed`, the
+    /// rule only gives the grid.
+    pub dust: Option<Selection>,
+    /// Multi-scale construction: when not empty, these maps replace the rule
+    /// and the dust.
+    #[serde(skip_serializing_if = "Maps::is_empty")]
+    pub maps: Maps,
+    /// Still view drawn: the square, or the 1D set of the top row of the rule.
+    pub figure: Figure,
+    /// Seed of the random draws that made the project (the last « Mystère »).
+    pub seed: Seed,
+    /// Camera path of the animation export.
+    pub camera: CameraPath,
+    pub export: Export,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Export {
+    /// Side of the PNG, JPEG and SVG exports in pixels.
+    pub image_size: u32,
+    /// Side of the animation frames in pixels.
+    pub animation_size: u32,
+    pub frames: u32,
+    pub timing: Timing,
+    pub fps: u32,
+    pub quality: u32,
+    /// Options of the still image formats.
+    pub image: ImageOptions,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            rule: Rule::cantor(),
+            iterations: 1,
+            zoom: 1.0,
+            target: [0.0, 0.0],
+            selected_color: 0,
+            palette: vec![
+                [239, 71, 111, 255],  // rose/rouge flashy
+                [255, 209, 102, 255], // jaune pastel
+                [6, 214, 160, 255],   // vert menthe
+                [17, 138, 178, 255],  // bleu vif
+                [155, 111, 242, 255], // violet
+            ],
+            coloring: Coloring::Uniform,
+            dust: None,
+            maps: Maps::default(),
+            figure: Figure::Square,
+            seed: 0,
+            camera: default_camera_path(23, 1.05),
+            export: Export::default(),
+        }
+    }
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        let video = VideoOptions::default();
+        Self {
+            image_size: 2187, // 3^7 pour être divisible
+            animation_size: 800,
+            frames: 23,
+            timing: Timing::default(),
+            fps: video.fps,
+            quality: video.quality,
+            image: ImageOptions::default(),
+        }
+    }
+}
+
+/// Zoom towards the top-left corner, `zoom_speed` per frame.
+pub fn default_camera_path(frames: u32, zoom_speed: f64) -> CameraPath {
+    let end_zoom = zoom_speed.powi(frames as i32 - 1);
+    CameraPath::new(vec![
+        Keyframe {
+            time: 0.0,
+            center: [0.5, 0.5],
+            zoom: 1.0,
+            rotation: 0.0,
+            easing: Easing::Linear,
+        },
+        Keyframe {
+            time: 1.0,
+            center: [0.5 / end_zoom, 0.5 / end_zoom],
+            zoom: end_zoom,
+            rotation: 0.0,
+            easing: Easing::Linear,
+        },
+    ])
+}
+
+impl Project {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let mut project: Project = toml::from_str(&fs::read_to_string(path)?)?;
+        project.validate()?;
+        project.camera.sort();
+        Ok(project)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.palette.is_empty() {
+            return Err("la palette est vide".into());
+        }
+        if self.selected_color >= self.palette.len() {
+            return Err(format!("couleur {} absente de la palette", self.selected_color).into());
+        }
+        if self.zoom.is_nan() || self.zoom < 1.0 {
+            return Err("le zoom doit être supérieur ou égal à 1".into());
+        }
+        if self.target.iter().any(|t| !(0.0..=1.0).contains(t)) {
+            return Err("la cible du zoom doit être dans le carré unité".into());
+        }
+        if let Some(selection) = &self.dust {
+            selection.validate(self.rule.grid)?;
+        }
+        self.maps.validate()?;
+        if self.export.image_size == 0 || self.export.animation_size == 0 || self.export.frames == 0 {
+            return Err("les tailles d'export et le nombre de frames doivent être positifs".into());
+        }
+        if self.export.fps == 0 {
+            return Err("fps doit être positif".into());
+        }
+        self.export.image.validate()?;
+        self.camera.validate()
+    }
+
+    pub fn color(&self) -> Rgba<u8> {
+        Rgba(self.palette[self.selected_color.min(self.palette.len() - 1)])
+    }
+
+    /// Style of the animation frames: the selected color on white.
+    pub fn style(&self) -> Style {
+        Style {
+            background: WHITE.0,
+            fill: self.color().0,
+            border: None,
+            coloring: self.coloring,
+            palette: self.palette.clone(),
+        }
+    }
+
+    /// Iterations down to squares of one pixel in a `size`-pixel still view.
+    /// The maps have no single ratio and stop at one pixel by themselves, so
+    /// they get `u32::MAX`.
+    pub fn pixel_depth(&self, size: f64) -> u32 {
+        if self.maps.is_empty() {
+            rule::depth_limit(self.rule.grid, size * self.still_camera().zoom)
+        } else {
+            u32::MAX
+        }
+    }
+
+    /// Random dust of the project, drawn with its grid and seed.
+    pub fn dust(&self) -> Option<Dust> {
+        self.dust.map(|selection| Dust {
+            grid: self.rule.grid,
+            selection,
+            seed: self.seed,
+        })
+    }
+
+    /// Calls `visit(x, y, side, color)` for each square of a `size`-pixel
+    /// drawing of the whole construction.
+    fn squares(&self, size: u32, mut visit: impl FnMut(u32, u32, u32, Rgba<u8>)) {
+        let fill = self.color().0;
+        let mut visit = |x, y, side, path: &[u32]| {
+            visit(x, y, side, Rgba(self.coloring.pick(path, fill, &self.palette)));
+        };
+        if !self.maps.is_empty() {
+            return self.maps.squares(0, 0, size, self.iterations, &mut visit);
+        }
+        match self.dust() {
+            Some(dust) => dust.squares(0, 0, size, self.iterations, &mut visit),
+            None => self.rule.squares(0, 0, size, self.iterations, &mut visit),
+        }
+    }
+
+    /// Top-left corner and side of the zoomed view in a `size`-unit drawing.
+    fn view(&self, size: f32) -> (f32, f32, f32) {
+        let view_size = size / self.zoom.max(1.0);
+        let [tx, ty] = self.target;
+        (tx * (size - view_size), ty * (size - view_size), view_size)
+    }
+
+    /// Camera showing the zoomed view of the still image, for
+    /// [`render::render_rgba`].
+    pub fn still_camera(&self) -> Camera {
+        let zoom = self.zoom.max(1.0) as f64;
+        let (start_x, start_y, _) = self.view(1.0);
+        Camera {
+            center: [start_x as f64 + 0.5 / zoom, start_y as f64 + 0.5 / zoom],
+            zoom,
+            rotation: 0.0,
+        }
+    }
+
+    /// Entries stored in the exported files: the look of the still view one
+    /// value per key, then the whole project to render it again.
+    pub fn metadata(&self) -> Entries {
+        let palette: Vec<String> = self
+            .palette
+            .iter()
+            .map(|[r, g, b, a]| match a {
+                255 => format!("#{:02x}{:02x}{:02x}", r, g, b),
+                a => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+            })
+            .collect();
+        let mut entries = metadata::generator();
+        entries.extend([
+            entry(metadata::RULE, &self.rule),
+            entry(metadata::ITERATIONS, self.iterations),
+            entry(metadata::PALETTE, palette.join(",")),
+            entry(metadata::COLOR, self.selected_color),
+            entry(metadata::COLORING, self.coloring),
+            entry(metadata::DUST, self.dust.map_or("aucun".to_string(), |selection| selection.to_string())),
+            entry(metadata::MAPS, if self.maps.is_empty() { "aucune".to_string() } else { self.maps.to_string() }),
+            entry(metadata::FIGURE, self.figure),
+            entry(metadata::ZOOM, self.zoom),
+            entry(metadata::TARGET, format!("{},{}", self.target[0], self.target[1])),
+            entry(metadata::SEED, self.seed),
+        ]);
+        if let Ok(project) = toml::to_string(self) {
+            entries.push(entry(metadata::PROJECT, project));
+        }
+        entries
+    }
+
+    /// Project that made an export, from its metadata: the embedded project
+    /// when there is one, otherwise the defaults with the entries found.
+    pub fn from_metadata(entries: &[(String, String)]) -> Result<Self, Box<dyn Error>> {
+        let mut project = match metadata::get(entries, metadata::PROJECT) {
+            Some(text) => toml::from_str(text)?,
+            None => {
+                let mut project = Project::default();
+                let get = |key| metadata::get(entries, key);
+                if let Some(rule) = get(metadata::RULE) {
+                    project.rule = rule.parse()?;
+                }
+                if let Some(iterations) = get(metadata::ITERATIONS) {
+                    project.iterations = iterations.parse()?;
+                }
+                if let Some(palette) = get(metadata::PALETTE) {
+                    project.palette = palette.split(',').map(cli::parse_rgba).collect::<Result<_, _>>()?;
+                }
+                if let Some(color) = get(metadata::COLOR) {
+                    project.selected_color = color.parse()?;
+                }
+                if let Some(coloring) = get(metadata::COLORING) {
+                    project.coloring = coloring.parse()?;
+                }
+                if let Some(dust) = get(metadata::DUST).filter(|&dust| dust != "aucun") {
+                    project.dust = Some(dust.parse()?);
+                }
+                if let Some(maps) = get(metadata::MAPS).filter(|&maps| maps != "aucune") {
+                    project.maps = maps.parse()?;
+                }
+                if let Some(figure) = get(metadata::FIGURE) {
+                    project.figure = figure.parse()?;
+                }
+                if let Some(zoom) = get(metadata::ZOOM) {
+                    project.zoom = zoom.parse()?;
+                }
+                if let Some(target) = get(metadata::TARGET) {
+                    project.target = cli::parse_pair(target)?;
+                }
+                if let Some(seed) = get(metadata::SEED) {
+                    project.seed = seed.parse()?;
+                }
+                project
+            }
+        };
+        project.validate()?;
+        project.camera.sort();
+        Ok(project)
+    }
+
+    pub fn animation_options(&self) -> AnimationOptions {
+        AnimationOptions {
+            plays: self.export.timing.plays,
+            video: VideoOptions {
+                fps: self.export.fps,
+                quality: self.export.quality,
+            },
+            metadata: self.metadata(),
+        }
+    }
+
+    /// Still view of `size × size` pixels. The 1D figures ignore the zoom.
+    pub fn render_image(&self, size: u32) -> RgbaImage {
+        let interval = Interval::from_rule(&self.rule);
+        match self.figure {
+            Figure::Square => {}
+            Figure::Bars => return interval.render_bars(size, self.iterations, &self.style()),
+            Figure::Staircase => return interval.render_staircase(size, self.iterations, &self.style()),
+        }
+
+        let mut full_image = RgbaImage::from_pixel(size, size, WHITE);
+        self.squares(size, |x, y, side, color| {
+            rule::fill_square(&mut full_image, x, y, side, color);
+        });
+
+        if self.zoom <= 1.0 {
+            return full_image;
+        }
+
+        let (start_x, start_y, view_size) = self.view(size as f32);
+        let max = size.saturating_sub(1);
+        RgbaImage::from_fn(size, size, |px, py| {
+            let src_x = start_x + px as f32 * view_size / size as f32;
+            let src_y = start_y + py as f32 * view_size / size as f32;
+            *full_image.get_pixel((src_x as u32).min(max), (src_y as u32).min(max))
+        })
+    }
+
+    /// Saves the still view in the format given by the extension of `path`,
+    /// with the export options of the project and its metadata when the
+    /// format has room for it.
+    pub fn export_image(&self, path: &str, size: u32) -> Result<(), Box<dyn Error>> {
+        // Unknown extensions fail before the render
+        ImageFormat::from_path(path)?;
+        format::save(&self.render_image(size), path, &self.metadata(), &self.export.image)
+    }
+
+    /// Vector version of the still view; the zoom only moves the viewBox.
+    pub fn render_svg(&self, size: u32) -> Document {
+        let (start_x, start_y, view_size) = match self.figure {
+            Figure::Square => self.view(size as f32),
+            Figure::Bars | Figure::Staircase => (0.0, 0.0, size as f32),
+        };
+        let document = Document::new()
+            .set("width", size)
+            .set("height", size)
+            .set("viewBox", (start_x, start_y, view_size, view_size))
+            .add(metadata::svg_element(&self.metadata()))
+            .add(
+                Rectangle::new()
+                    .set("x", 0)
+                    .set("y", 0)
+                    .set("width", size)
+                    .set("height", size)
+                    .set("fill", "white"),
+            );
+
+        let interval = Interval::from_rule(&self.rule);
+        let fill = self.color().0;
+        let rgb = |[r, g, b, _]: [u8; 4]| format!("rgb({},{},{})", r, g, b);
+        match self.figure {
+            Figure::Square => {}
+            Figure::Bars => {
+                return interval.bars(size, self.iterations).into_iter().fold(document, |document, bar| {
+                    document.add(
+                        Rectangle::new()
+                            .set("x", bar.x)
+                            .set("y", bar.y)
+                            .set("width", bar.width)
+                            .set("height", bar.height)
+                            .set("fill", rgb(self.coloring.pick(&bar.path, fill, &self.palette))),
+                    )
+                });
+            }
+            Figure::Staircase => {
+                let points: Vec<String> = interval
+                    .staircase(size, self.iterations)
+                    .iter()
+                    .map(|[x, y]| format!("{},{}", x, y))
+                    .collect();
+                let (low, side) = (size as f64 * 0.05, size as f64 * 0.9);
+                return document
+                    .add(
+                        Rectangle::new()
+                            .set("x", low)
+                            .set("y", low)
+                            .set("width", side)
+                            .set("height", side)
+                            .set("fill", "none")
+                            .set("stroke", "rgb(200,200,200)"),
+                    )
+                    .add(
+                        Polyline::new()
+                            .set("points", points.join(" "))
+                            .set("fill", "none")
+                            .set("stroke", rgb(fill))
+                            .set("stroke-width", (size as f64 / 400.0).max(1.0) + 1.0),
+                    );
+            }
+        }
+
+        let mut squares = Vec::new();
+        self.squares(size, |x, y, side, color| squares.push((x, y, side, color)));
+        squares.into_iter().fold(document, |document, (x, y, side, color)| {
+            document.add(
+                Rectangle::new()
+                    .set("x", x)
+                    .set("y", y)
+                    .set("width", side)
+                    .set("height", side)
+                    .set("fill", format!("rgb({},{},{})", color[0], color[1], color[2])),
+            )
+        })
+    }
+
+    /// Renders the camera path of the project into `path`, in the format
+    /// given by its extension. `progress(frame, frames)` is called once each
+    /// frame is written, from 1.
+    pub fn export_animation(&self, path: &str, mut progress: impl FnMut(u32, u32)) -> Result<(), Box<dyn Error>> {
+        self.camera.validate()?;
+        let Export { animation_size: size, frames, .. } = self.export;
+        let mut writer = animation::create(path, size, size, frames, &self.animation_options())?;
+        let style = self.style();
+        let dust = self.dust();
+        let delays = self.export.timing.delays(frames);
+
+        pipeline::stream(
+            frames,
+            pipeline::default_lookahead(),
+            |frame_num| {
+                let camera = self.camera.frame(frame_num, frames);
+                render::render_rgba(&camera, size as usize, size as usize, &self.rule, dust.as_ref(), &self.maps, self.iterations, &style)
+            },
+            |frame_num, image| {
+                writer.write_frame(&image, delays[frame_num as usize])?;
+                progress(frame_num + 1, frames);
+                Ok(())
+            },
+        )?;
+
+        writer.finish()
+    }
+}
@@ -0,0 +1,153 @@
+//! Seeded exploration of the rule space for the « Mystère » button.
+//!
+//! Every draw is made in the same order whatever the locks, so a seed gives
+//! the same unlocked values when it is replayed with other locks.
+//!
+//! All random choices go through [`seeded`]: ChaCha8 gives the same stream
+//! on every platform and rand version, unlike `StdRng`.
+
+use crate::affine::Maps;
+use crate::project::Project;
+use crate::render::Coloring;
+use crate::rule::{self, Rule};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Parameters kept from the current project when rerolling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Locks {
+    pub rule: bool,
+    pub iterations: bool,
+    /// Palette and selected color.
+    pub palette: bool,
+    pub coloring: bool,
+    /// Zoom and zoom target.
+    pub view: bool,
+}
+
+/// Seeds are kept to 32 bits: short to share, and they fit in a TOML integer.
+pub type Seed = u32;
+
+/// Seed for a new roll, the only value taken from the system entropy.
+pub fn new_seed() -> Seed {
+    rand::rng().random()
+}
+
+/// Reproducible random generator for `seed`.
+pub fn seeded(seed: Seed) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed as u64)
+}
+
+/// Random variation of `project` for `seed`, keeping the locked parameters.
+/// Camera and export settings are never changed.
+pub fn randomize(project: &Project, seed: Seed, locks: &Locks) -> Project {
+    let mut rng = seeded(seed);
+    let rule = random_rule(&mut rng);
+    // Deep enough to show the structure, shallow enough for the preview
+    let iterations = rng.random_range(2..=rule::depth_limit(rule.grid, 729.0).clamp(2, 6));
+    let palette = random_palette(&mut rng);
+    let selected_color = rng.random_range(0..palette.len());
+    let coloring = Coloring::ALL[rng.random_range(0..Coloring::ALL.len())];
+
+    let mut result = project.clone();
+    result.seed = seed;
+    if !locks.rule {
+        result.rule = rule;
+        // Multi-scale maps would hide the new rule
+        result.maps = Maps::default();
+    }
+    if !locks.iterations {
+        result.iterations = iterations;
+    }
+    if !locks.palette {
+        result.palette = palette;
+        result.selected_color = selected_color;
+    }
+    if !locks.coloring {
+        result.coloring = coloring;
+    }
+    // Drawn last: the target follows the kept cells of the final rule
+    let (zoom, target) = random_view(&mut rng, &result.rule);
+    if !locks.view {
+        result.zoom = zoom;
+        result.target = target;
+    }
+    result
+}
+
+/// Grid of 2 to 5 cells per side, mostly 3, keeping between 2 cells and
+/// all the cells but one so the limit set is neither a point nor a square.
+fn random_rule(rng: &mut ChaCha8Rng) -> Rule {
+    let grid = [2, 3, 3, 3, 4, 4, 5][rng.random_range(0..7)];
+    let cells = (grid * grid) as usize;
+    let density = rng.random_range(0.3..0.7);
+    loop {
+        let keep: Vec<bool> = (0..cells).map(|_| rng.random_bool(density)).collect();
+        let kept = keep.iter().filter(|&&keep| keep).count();
+        if (2..cells).contains(&kept) {
+            return Rule { grid, keep };
+        }
+    }
+}
+
+/// Five colors around a random hue: analogous, complementary or triadic.
+fn random_palette(rng: &mut ChaCha8Rng) -> Vec<[u8; 4]> {
+    let hue = rng.random_range(0.0..360.0);
+    let offsets: [f64; 5] = match rng.random_range(0..3) {
+        0 => [0.0, 25.0, 50.0, -25.0, -50.0],
+        1 => [0.0, 180.0, 20.0, 200.0, -20.0],
+        _ => [0.0, 120.0, 240.0, 60.0, 180.0],
+    };
+    offsets
+        .iter()
+        .map(|offset| {
+            let saturation = rng.random_range(0.55..0.9);
+            let value = rng.random_range(0.6..0.95);
+            hsv_to_rgba(hue + offset, saturation, value)
+        })
+        .collect()
+}
+
+/// Zoom of 1 two times out of three, otherwise up to x4 centered on a
+/// square of the second level, found by following random kept cells.
+fn random_view(rng: &mut ChaCha8Rng, rule: &Rule) -> (f32, [f32; 2]) {
+    let zoomed = rng.random_bool(1.0 / 3.0);
+    let zoom = rng.random_range(2..=4) as f64;
+    let cells: Vec<(u32, u32)> = rule.cells().collect();
+    let grid = rule.grid as f64;
+    let (mut x, mut y, mut side) = (0.0, 0.0, 1.0);
+    for _ in 0..2 {
+        if cells.is_empty() {
+            break;
+        }
+        let (cx, cy) = cells[rng.random_range(0..cells.len())];
+        side /= grid;
+        x += cx as f64 * side;
+        y += cy as f64 * side;
+    }
+    if !zoomed {
+        return (1.0, [0.0, 0.0]);
+    }
+
+    // The view of side 1/zoom starts at target × (1 - 1/zoom)
+    let view = 1.0 / zoom;
+    let target = |point: f64| ((point + side / 2.0 - view / 2.0) / (1.0 - view)).clamp(0.0, 1.0) as f32;
+    (zoom as f32, [target(x), target(y)])
+}
+
+fn hsv_to_rgba(hue: f64, saturation: f64, value: f64) -> [u8; 4] {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b), 255]
+}
@@ -0,0 +1,265 @@
+//! CPU ray-casting of the 3D constructions of [`SolidRule`], for still
+//! previews and orbit animations.
+//!
+//! Rays walk the subdivision tree: a 3D DDA steps through the `grid³` cells
+//! of a cube and only descends into the kept ones, so empty regions are
+//! skipped at the coarsest level that contains them.
+
+use crate::solid::SolidRule;
+use image::RgbaImage;
+use rayon::prelude::*;
+use std::error::Error;
+
+/// Camera on a sphere around the center of the unit cube, `z` up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+    /// Angle around the vertical axis, in degrees.
+    pub yaw: f64,
+    /// Height angle above the horizontal plane, in degrees.
+    pub pitch: f64,
+    /// Distance to the center, in units of the cube side.
+    pub distance: f64,
+    /// Vertical field of view, in degrees.
+    pub fov: f64,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            yaw: 35.0,
+            pitch: 25.0,
+            distance: 2.6,
+            fov: 40.0,
+        }
+    }
+}
+
+impl Orbit {
+    /// Position of frame `frame` of a full turn of `frames` frames.
+    pub fn turn(&self, frame: u32, frames: u32) -> Self {
+        Self {
+            yaw: self.yaw + 360.0 * frame as f64 / frames.max(1) as f64,
+            ..*self
+        }
+    }
+
+    fn eye(&self) -> [f64; 3] {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        [
+            0.5 + self.distance * pitch.cos() * yaw.cos(),
+            0.5 + self.distance * pitch.cos() * yaw.sin(),
+            0.5 + self.distance * pitch.sin(),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Shading {
+    pub background: [u8; 4],
+    pub color: [u8; 4],
+    /// Direction towards the light.
+    pub light: [f64; 3],
+    /// Share of the light that reaches the faces in shadow.
+    pub ambient: f64,
+    /// Rays cast around each hit for ambient occlusion, 0 to disable.
+    pub ao_samples: u32,
+    /// Hard shadows of the directional light.
+    pub shadows: bool,
+}
+
+impl Default for Shading {
+    fn default() -> Self {
+        Self {
+            background: [245, 245, 248, 255],
+            color: [239, 71, 111, 255],
+            light: [0.4, 0.25, 0.9],
+            ambient: 0.3,
+            ao_samples: 8,
+            shadows: true,
+        }
+    }
+}
+
+struct Hit {
+    t: f64,
+    normal: [f64; 3],
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+    a.map(|c| c * s)
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(a: [f64; 3]) -> [f64; 3] {
+    scale(a, 1.0 / dot(a, a).sqrt().max(f64::MIN_POSITIVE))
+}
+
+/// The construction of `rule` after `iterations` steps, in the unit cube.
+pub struct Scene<'a> {
+    rule: &'a SolidRule,
+    iterations: u32,
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(rule: &'a SolidRule, iterations: u32) -> Self {
+        Self { rule, iterations }
+    }
+
+    /// Entry and exit distances of the ray in the box, and the axis it enters
+    /// through.
+    fn slab(origin: [f64; 3], dir: [f64; 3], corner: [f64; 3], side: f64) -> Option<(f64, f64, usize)> {
+        let (mut near, mut far, mut axis) = (f64::NEG_INFINITY, f64::INFINITY, 0);
+        for i in 0..3 {
+            if dir[i] == 0.0 {
+                // Parallel to the slab: inside it or never
+                if origin[i] < corner[i] || origin[i] > corner[i] + side {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1.0 / dir[i];
+            let (a, b) = ((corner[i] - origin[i]) * inverse, (corner[i] + side - origin[i]) * inverse);
+            let (a, b) = if a <= b { (a, b) } else { (b, a) };
+            if a > near {
+                near = a;
+                axis = i;
+            }
+            far = far.min(b);
+        }
+        (near <= far && far > 0.0).then_some((near, far, axis))
+    }
+
+    /// First hit of the ray after `t_min`.
+    fn hit(&self, origin: [f64; 3], dir: [f64; 3], t_min: f64) -> Option<Hit> {
+        self.hit_cube(origin, dir, [0.0; 3], 1.0, self.iterations, t_min)
+    }
+
+    fn hit_cube(&self, origin: [f64; 3], dir: [f64; 3], corner: [f64; 3], side: f64, depth: u32, t_min: f64) -> Option<Hit> {
+        let (near, far, axis) = Self::slab(origin, dir, corner, side)?;
+        if far < t_min {
+            return None;
+        }
+        if depth == 0 {
+            // Starting inside a cube (from a surface): no hit in this one
+            if near < t_min {
+                return None;
+            }
+            let mut normal = [0.0; 3];
+            normal[axis] = -dir[axis].signum();
+            return Some(Hit { t: near, normal });
+        }
+
+        // DDA through the grid³ cells, from the entry point
+        let grid = self.rule.grid as i64;
+        let sub = side / grid as f64;
+        let start = near.max(t_min);
+        let point = add(origin, scale(dir, start));
+        let mut cell = [0, 1, 2].map(|i| (((point[i] - corner[i]) / sub).floor() as i64).clamp(0, grid - 1));
+        let step = dir.map(|d| if d >= 0.0 { 1 } else { -1 });
+        let mut next = [0, 1, 2].map(|i| {
+            let boundary = corner[i] + (cell[i] + (step[i] > 0) as i64) as f64 * sub;
+            if dir[i] == 0.0 { f64::INFINITY } else { (boundary - origin[i]) / dir[i] }
+        });
+        let delta = dir.map(|d| if d == 0.0 { f64::INFINITY } else { sub / d.abs() });
+
+        loop {
+            if self.rule.keeps(cell[0] as u32, cell[1] as u32, cell[2] as u32) {
+                let child = [0, 1, 2].map(|i| corner[i] + cell[i] as f64 * sub);
+                if let Some(hit) = self.hit_cube(origin, dir, child, sub, depth - 1, t_min) {
+                    return Some(hit);
+                }
+            }
+            let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b])).unwrap_or(0);
+            if next[axis] > far {
+                return None;
+            }
+            cell[axis] += step[axis];
+            if !(0..grid).contains(&cell[axis]) {
+                return None;
+            }
+            next[axis] += delta[axis];
+        }
+    }
+
+    /// Share of a hemisphere around `normal` left open within `reach`, from
+    /// fixed directions turned by `turn` so that the pattern does not show.
+    fn occlusion(&self, point: [f64; 3], normal: [f64; 3], samples: u32, reach: f64, turn: f64) -> f64 {
+        // Basis around the normal
+        let helper = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+        let u = normalize(cross(normal, helper));
+        let v = cross(normal, u);
+        let mut open = 0;
+        for i in 0..samples {
+            // Cosine-weighted spiral over the hemisphere
+            let r = ((i as f64 + 0.5) / samples as f64).sqrt();
+            let angle = i as f64 * 2.399_963 + turn;
+            let (x, y) = (r * angle.cos(), r * angle.sin());
+            let dir = add(add(scale(u, x), scale(v, y)), scale(normal, (1.0 - r * r).max(0.0).sqrt()));
+            if self.hit(point, dir, 1e-9).is_none_or(|hit| hit.t > reach) {
+                open += 1;
+            }
+        }
+        open as f64 / samples.max(1) as f64
+    }
+
+    /// Renders a `width × height` image, one row per rayon task.
+    pub fn render(&self, orbit: &Orbit, width: u32, height: u32, shading: &Shading) -> Result<RgbaImage, Box<dyn Error>> {
+        if width == 0 || height == 0 {
+            return Err("la taille de l'image doit être positive".into());
+        }
+        let eye = orbit.eye();
+        let forward = normalize(add([0.5; 3], scale(eye, -1.0)));
+        let right = normalize(cross(forward, [0.0, 0.0, 1.0]));
+        let up = cross(right, forward);
+        let half = (orbit.fov.to_radians() / 2.0).tan();
+        let light = normalize(shading.light);
+        // Occlusion reaches a few of the smallest cubes
+        let reach = 3.0 * (self.rule.grid as f64).powi(-(self.iterations as i32));
+        let bias = 1e-7;
+
+        let mut image = RgbaImage::new(width, height);
+        image
+            .par_chunks_mut(width as usize * 4)
+            .enumerate()
+            .for_each(|(py, row)| {
+                for (px, rgba) in row.chunks_exact_mut(4).enumerate() {
+                    let x = (2.0 * (px as f64 + 0.5) / width as f64 - 1.0) * half * width as f64 / height as f64;
+                    let y = (1.0 - 2.0 * (py as f64 + 0.5) / height as f64) * half;
+                    let dir = normalize(add(forward, add(scale(right, x), scale(up, y))));
+                    let Some(hit) = self.hit(eye, dir, 0.0) else {
+                        rgba.copy_from_slice(&shading.background);
+                        continue;
+                    };
+
+                    let point = add(add(eye, scale(dir, hit.t)), scale(hit.normal, bias));
+                    let mut diffuse = dot(hit.normal, light).max(0.0);
+                    if diffuse > 0.0 && shading.shadows && self.hit(point, light, 1e-9).is_some() {
+                        diffuse = 0.0;
+                    }
+                    let occlusion = if shading.ao_samples > 0 {
+                        let turn = (px * 7 + py * 13) as f64 * 0.618;
+                        self.occlusion(point, hit.normal, shading.ao_samples, reach, turn)
+                    } else {
+                        1.0
+                    };
+                    let light = (shading.ambient * occlusion + (1.0 - shading.ambient) * diffuse).clamp(0.0, 1.0);
+                    for (channel, value) in rgba.iter_mut().enumerate().take(3) {
+                        *value = (shading.color[channel] as f64 * light).round() as u8;
+                    }
+                    rgba[3] = shading.color[3];
+                }
+            });
+        Ok(image)
+    }
+}
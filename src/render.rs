@@ -0,0 +1,268 @@
+//! Per-pixel rasterizer for the Cantor dust seen through a [`Camera`].
+
+use crate::affine::Maps;
+use crate::camera::Camera;
+use crate::dust::Dust;
+use crate::rule::{self, Rule};
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the squares of the last iteration are colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Coloring {
+    /// Every square in the fill color.
+    #[default]
+    Uniform,
+    /// One palette color per cell of the first subdivision.
+    Branch,
+    /// Palette color picked from the cells chosen at every level.
+    Mosaic,
+}
+
+impl Coloring {
+    pub const ALL: [Coloring; 3] = [Coloring::Uniform, Coloring::Branch, Coloring::Mosaic];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Coloring::Uniform => "Uni",
+            Coloring::Branch => "Branches",
+            Coloring::Mosaic => "Mosaïque",
+        }
+    }
+
+    /// Color of a square reached through `path` (kept-cell ranks, one per level).
+    pub fn pick(self, path: &[u32], fill: [u8; 4], palette: &[[u8; 4]]) -> [u8; 4] {
+        if palette.is_empty() || path.is_empty() {
+            return fill;
+        }
+        let index = match self {
+            Coloring::Uniform => return fill,
+            Coloring::Branch => path[0],
+            Coloring::Mosaic => path.iter().sum(),
+        };
+        palette[index as usize % palette.len()]
+    }
+}
+
+/// Written like in project files: `uniform`, `branch` or `mosaic`.
+impl fmt::Display for Coloring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Coloring::Uniform => "uniform",
+            Coloring::Branch => "branch",
+            Coloring::Mosaic => "mosaic",
+        })
+    }
+}
+
+impl FromStr for Coloring {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "uniform" | "uni" => Ok(Coloring::Uniform),
+            "branch" | "branches" => Ok(Coloring::Branch),
+            "mosaic" | "mosaïque" => Ok(Coloring::Mosaic),
+            _ => Err(format!("coloration inconnue: {} (uniform, branch, mosaic)", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub background: [u8; 4],
+    pub fill: [u8; 4],
+    /// Outline drawn around each square of the last iteration.
+    pub border: Option<[u8; 4]>,
+    pub coloring: Coloring,
+    /// Colors of the branch and mosaic colorings.
+    pub palette: Vec<[u8; 4]>,
+}
+
+enum Hit {
+    Outside,
+    Fill,
+    Border,
+}
+
+/// Ranks of the dust masks met last, one per level: neighbouring pixels fall
+/// in the same squares, so each row only draws a mask when it enters a new one.
+#[derive(Default)]
+struct Masks(Vec<Option<Mask>>);
+
+#[derive(Clone)]
+struct Mask {
+    cell: [u64; 2],
+    ranks: Vec<Option<u32>>,
+}
+
+impl Masks {
+    fn ranks(&mut self, dust: &Dust, level: u32, cell: [u64; 2]) -> &[Option<u32>] {
+        let level = level as usize;
+        if self.0.len() <= level {
+            self.0.resize(level + 1, None);
+        }
+        let mask = &mut self.0[level];
+        if mask.as_ref().is_none_or(|mask| mask.cell != cell) {
+            let mut next = 0;
+            let ranks = dust
+                .mask(level as u32, cell)
+                .into_iter()
+                .map(|keep| {
+                    keep.then(|| {
+                        next += 1;
+                        next - 1
+                    })
+                })
+                .collect();
+            *mask = Some(Mask { cell, ranks });
+        }
+        &mask.as_ref().expect("mask drawn above").ranks
+    }
+}
+
+/// Settings of one frame for [`Classifier::classify`].
+struct Classifier {
+    grid: f64,
+    /// Kept-cell ranks of the rule, see [`Rule::kept_indices`].
+    ranks: Vec<Option<u32>>,
+    /// Random masks replacing the rule one.
+    dust: Option<Dust>,
+    /// Multi-scale maps replacing the grid, when not empty.
+    maps: Maps,
+    iterations: u32,
+    pixel: f64,
+    with_border: bool,
+}
+
+impl Classifier {
+    /// Follows the point `(x, y)` of the unit square down the construction,
+    /// recording the rank of the kept cell it falls in at each level.
+    fn classify(&self, x: f64, y: f64, path: &mut Vec<u32>, masks: &mut Masks) -> Hit {
+        path.clear();
+        if !(0.0..1.0).contains(&x) || !(0.0..1.0).contains(&y) {
+            return Hit::Outside;
+        }
+        if !self.maps.is_empty() {
+            return self.classify_maps(x, y, path);
+        }
+
+        let grid = self.grid;
+        let (mut x, mut y, mut side) = (x, y, 1.0);
+        // Position of the current square, in units of its side
+        let mut cell = [0u64; 2];
+        for level in 0..self.iterations {
+            x *= grid;
+            y *= grid;
+            let (cx, cy) = (x.floor().min(grid - 1.0), y.floor().min(grid - 1.0));
+            let index = (cy * grid + cx) as usize;
+            let rank = match &self.dust {
+                Some(dust) => masks.ranks(dust, level, cell)[index],
+                None => self.ranks[index],
+            };
+            match rank {
+                Some(rank) => path.push(rank),
+                None => return Hit::Outside,
+            }
+            let n = grid as u64;
+            cell = [
+                cell[0].wrapping_mul(n).wrapping_add(cx as u64),
+                cell[1].wrapping_mul(n).wrapping_add(cy as u64),
+            ];
+            x -= cx;
+            y -= cy;
+            side /= grid;
+        }
+
+        self.hit(x, y, side)
+    }
+
+    /// Same walk through the maps: the point goes into the first image that
+    /// contains it, until the images get smaller than a pixel.
+    fn classify_maps(&self, x: f64, y: f64, path: &mut Vec<u32>) -> Hit {
+        let (mut point, mut side) = ([x, y], 1.0);
+        for _ in 0..self.iterations {
+            if !self.maps.subdivides(side, self.pixel) {
+                break;
+            }
+            let found = self.maps.0.iter().enumerate().find_map(|(index, map)| {
+                map.inverse(point).map(|inner| (index, inner, map.ratio))
+            });
+            let Some((index, inner, ratio)) = found else {
+                return Hit::Outside;
+            };
+            path.push(index as u32);
+            point = inner;
+            side *= ratio;
+        }
+        self.hit(point[0], point[1], side)
+    }
+
+    /// Fill or border for a point `(x, y)` of a final square of side `side`.
+    fn hit(&self, x: f64, y: f64, side: f64) -> Hit {
+        let edge = self.pixel / side;
+        if self.with_border && (x < edge || y < edge || x >= 1.0 - edge || y >= 1.0 - edge) {
+            Hit::Border
+        } else {
+            Hit::Fill
+        }
+    }
+}
+
+/// Renders a `width × height` frame, one row per rayon task.
+///
+/// `iterations` is clamped to the depth whose squares are still one pixel wide.
+/// With a `dust`, its random masks replace the mask of `rule`; non-empty
+/// `maps` replace both, their squares stopping at one pixel.
+#[allow(clippy::too_many_arguments)]
+pub fn render_rgba(
+    camera: &Camera,
+    width: usize,
+    height: usize,
+    rule: &Rule,
+    dust: Option<&Dust>,
+    maps: &Maps,
+    iterations: u32,
+    style: &Style,
+) -> RgbaImage {
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    let pixel = camera.pixel_size(width);
+    let classifier = Classifier {
+        grid: rule.grid as f64,
+        ranks: rule.kept_indices(),
+        dust: dust.copied(),
+        maps: maps.clone(),
+        // Squares smaller than a pixel would only be point-sampled noise; the
+        // maps check it at each level since their ratios differ
+        iterations: if maps.is_empty() {
+            iterations.min(rule::depth_limit(rule.grid, 1.0 / pixel))
+        } else {
+            iterations
+        },
+        pixel,
+        with_border: style.border.is_some(),
+    };
+
+    image
+        .par_chunks_mut(width * 4)
+        .enumerate()
+        .for_each(|(py, row)| {
+            let mut path = Vec::with_capacity(classifier.iterations as usize);
+            let mut masks = Masks::default();
+            for (px, rgba) in row.chunks_exact_mut(4).enumerate() {
+                let [x, y] = camera.screen_to_world(px as f64 + 0.5, py as f64 + 0.5, width, height);
+                let color = match classifier.classify(x, y, &mut path, &mut masks) {
+                    Hit::Outside => style.background,
+                    Hit::Fill => style.coloring.pick(&path, style.fill, &style.palette),
+                    Hit::Border => style.border.unwrap_or(style.fill),
+                };
+                rgba.copy_from_slice(&color);
+            }
+        });
+
+    image
+}
@@ -0,0 +1,210 @@
+//! Subdivision rules: each square is cut into a `grid × grid` array of cells
+//! and only the cells of the keep mask are subdivided again.
+
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Serialized as its `grid:mask` string, like on the command line.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rule {
+    pub grid: u32,
+    /// Row-major keep mask, `grid × grid` entries.
+    pub keep: Vec<bool>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::cantor()
+    }
+}
+
+impl Rule {
+    /// The Cantor square: thirds, keeping the 4 corners.
+    pub fn cantor() -> Self {
+        Self {
+            grid: 3,
+            keep: vec![true, false, true, false, false, false, true, false, true],
+        }
+    }
+
+    pub fn keeps(&self, x: u32, y: u32) -> bool {
+        self.keep[(y * self.grid + x) as usize]
+    }
+
+    /// Rank of each grid cell among the kept cells, row by row.
+    pub fn kept_indices(&self) -> Vec<Option<u32>> {
+        let mut next = 0;
+        self.keep
+            .iter()
+            .map(|&keep| {
+                keep.then(|| {
+                    next += 1;
+                    next - 1
+                })
+            })
+            .collect()
+    }
+
+    /// Kept cells, as `(x, y)` positions in the grid.
+    pub fn cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (0..self.grid)
+            .flat_map(move |y| (0..self.grid).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.keeps(x, y))
+    }
+
+    pub fn kept(&self) -> u32 {
+        self.keep.iter().filter(|&&keep| keep).count() as u32
+    }
+
+    /// Deepest iteration whose squares are still at least one pixel wide in
+    /// a `size`-pixel drawing.
+    pub fn max_depth(&self, size: u32) -> u32 {
+        depth_limit(self.grid, size as f64)
+    }
+
+    /// Draws the rule in a `size × size` square at `(x, y)`, like the
+    /// recursive Cantor square drawing.
+    pub fn draw(
+        &self,
+        image: &mut RgbaImage,
+        x: u32,
+        y: u32,
+        size: u32,
+        iterations: u32,
+        color: Rgba<u8>,
+    ) {
+        self.squares(x, y, size, iterations, &mut |x, y, side, _| {
+            fill_square(image, x, y, side, color);
+        });
+    }
+
+    /// Calls `visit(x, y, side, path)` for each square of the construction
+    /// in a `size × size` square at `(x, y)`. `path` holds the rank of the
+    /// kept cell chosen at each level, see [`Rule::kept_indices`].
+    ///
+    /// Squares never get smaller than one pixel: `iterations` is clamped to
+    /// [`Rule::max_depth`].
+    pub fn squares(
+        &self,
+        x: u32,
+        y: u32,
+        size: u32,
+        iterations: u32,
+        visit: &mut impl FnMut(u32, u32, u32, &[u32]),
+    ) {
+        if size == 0 {
+            return;
+        }
+        let iterations = iterations.min(self.max_depth(size));
+        let ranks = self.kept_indices();
+        let mut path = Vec::with_capacity(iterations as usize);
+        self.visit_squares(x, y, size, iterations, &ranks, &mut path, visit);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn visit_squares(
+        &self,
+        x: u32,
+        y: u32,
+        size: u32,
+        iterations: u32,
+        ranks: &[Option<u32>],
+        path: &mut Vec<u32>,
+        visit: &mut impl FnMut(u32, u32, u32, &[u32]),
+    ) {
+        if iterations == 0 {
+            visit(x, y, size, path);
+            return;
+        }
+
+        let sub_size = size / self.grid;
+        for (i, j) in self.cells() {
+            path.push(ranks[(j * self.grid + i) as usize].unwrap_or(0));
+            self.visit_squares(
+                x + i * sub_size,
+                y + j * sub_size,
+                sub_size,
+                iterations - 1,
+                ranks,
+                path,
+                visit,
+            );
+            path.pop();
+        }
+    }
+}
+
+/// Fills a square, clipped to the image.
+pub fn fill_square(image: &mut RgbaImage, x: u32, y: u32, size: u32, color: Rgba<u8>) {
+    for px in x..(x + size).min(image.width()) {
+        for py in y..(y + size).min(image.height()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Number of times a square of `cells` pixels can be cut in `grid × grid`
+/// before its cells get smaller than one pixel.
+pub fn depth_limit(grid: u32, cells: f64) -> u32 {
+    let grid = grid.max(2) as f64;
+    let mut side = cells.min(f64::MAX);
+    let mut depth = 0;
+    while side >= grid {
+        side /= grid;
+        depth += 1;
+    }
+    depth
+}
+
+/// Written `grid:mask`, the mask row by row with `1` for kept cells, e.g.
+/// `3:101000101` for the Cantor square.
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("règle invalide: {} (ex: 3:101000101)", value);
+        let (grid, mask) = value.split_once(':').ok_or_else(invalid)?;
+        let grid: u32 = grid.trim().parse().map_err(|_| invalid())?;
+        let keep = mask
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '/')
+            .map(|c| match c {
+                '1' | '#' => Ok(true),
+                '0' | '.' => Ok(false),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<bool>, String>>()?;
+
+        if grid < 2 || keep.len() != (grid * grid) as usize {
+            return Err(invalid());
+        }
+        Ok(Self { grid, keep })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.grid)?;
+        for &keep in &self.keep {
+            write!(f, "{}", if keep { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
@@ -0,0 +1,212 @@
+//! Local HTTP server of the XYZ tiles of a project, for deep zooms in a
+//! browser. Tiles are rendered on demand with [`tiles::xyz_tile`] and kept
+//! on disk, one cache directory per project.
+//!
+//! Only `GET /` (the viewer) and `GET /tiles/{z}/{x}/{y}.png` are answered,
+//! by one worker thread per core, and the socket is bound to localhost.
+
+use crate::project::Project;
+use crate::tiles;
+use image::{DynamicImage, ImageOutputFormat};
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
+
+/// Numbers the tiles being written to the cache.
+static PARTIAL: AtomicU64 = AtomicU64::new(0);
+/// Longest request read, headers included.
+const MAX_REQUEST: u64 = 8192;
+/// Time a client has to send its request, then to read the answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct ServerOptions {
+    pub port: u16,
+    /// Deepest level served.
+    pub max_zoom: u32,
+    /// Root of the tile cache, `None` to render every request again.
+    pub cache: Option<PathBuf>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            port: 8080,
+            // Beyond, the viewer positions lose pixel precision in JavaScript
+            max_zoom: 32,
+            cache: Some(PathBuf::from(".fracantor-cache")),
+        }
+    }
+}
+
+/// Directory of the cached tiles of `project` under `root`, named after a
+/// hash of its settings so that an edited project does not reuse old tiles.
+pub fn cache_dir(project: &Project, root: &Path) -> PathBuf {
+    // FNV-1a: stable from one build to the next, unlike the std hasher
+    let text = toml::to_string(project).unwrap_or_default();
+    let hash = text
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    root.join(format!("{:016x}", hash))
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Serves `project` on `127.0.0.1` until the process is stopped.
+pub fn serve(project: &Project, options: &ServerOptions) -> Result<(), Box<dyn Error>> {
+    // Refuse a project the tiles cannot draw before listening
+    tiles::xyz_tile(project, 0, 0, 0)?;
+    let cache = options.cache.as_ref().map(|root| cache_dir(project, root));
+    if let Some(dir) = &cache {
+        fs::create_dir_all(dir)?;
+    }
+    let listener = TcpListener::bind(("127.0.0.1", options.port))?;
+    println!("Serveur: http://127.0.0.1:{}/ (Ctrl+C pour arrêter)", options.port);
+    if let Some(dir) = &cache {
+        println!("Cache: {}", dir.display());
+    }
+
+    // Each tile already renders on every core: more workers would only
+    // share them, and a flood of requests waits in the listen backlog
+    let workers = thread::available_parallelism().map_or(4, |count| count.get());
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(workers);
+    let receiver = Mutex::new(receiver);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let (receiver, cache) = (&receiver, cache.as_deref());
+            scope.spawn(move || {
+                loop {
+                    // The lock is released before handling the request
+                    let next = receiver.lock().ok().and_then(|receiver| receiver.recv().ok());
+                    let Some(stream) = next else {
+                        break;
+                    };
+                    if let Err(err) = handle(stream, project, options, cache) {
+                        eprintln!("requête interrompue: {}", err);
+                    }
+                }
+            });
+        }
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("connexion refusée: {}", err);
+                    continue;
+                }
+            };
+            if sender.send(stream).is_err() {
+                break;
+            }
+        }
+        drop(sender);
+    });
+    Ok(())
+}
+
+fn handle(stream: TcpStream, project: &Project, options: &ServerOptions, cache: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Headers are not needed, only skipped
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => route(target.split('?').next().unwrap_or(target), project, options, cache),
+        (Some(_), Some(_)) => Response::error("405 Method Not Allowed", "seule la méthode GET est acceptée"),
+        _ => Response::error("400 Bad Request", "requête invalide"),
+    };
+
+    let mut stream = reader.into_inner().into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn route(path: &str, project: &Project, options: &ServerOptions, cache: Option<&Path>) -> Response {
+    if path == "/" || path == "/index.html" {
+        let page = tiles::viewer("tiles/{z}/{x}/{y}.png", options.max_zoom);
+        return Response::ok("text/html; charset=utf-8", page.into_bytes());
+    }
+    let Some(tile) = path.strip_prefix("/tiles/").and_then(parse_tile) else {
+        return Response::error("404 Not Found", "page inconnue");
+    };
+    if tile.0 > options.max_zoom {
+        return Response::error("404 Not Found", "niveau de zoom hors limites");
+    }
+    match tile_png(project, tile, cache) {
+        Ok(png) => Response::ok("image/png", png),
+        Err(err) => Response::error("404 Not Found", &err.to_string()),
+    }
+}
+
+/// `{z}/{x}/{y}.png`.
+fn parse_tile(path: &str) -> Option<(u32, u64, u64)> {
+    let mut parts = path.strip_suffix(".png")?.split('/');
+    let tile = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    parts.next().is_none().then_some(tile)
+}
+
+/// The tile from the cache, or rendered and then stored there.
+fn tile_png(project: &Project, (zoom, x, y): (u32, u64, u64), cache: Option<&Path>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let path = cache.map(|dir| dir.join(zoom.to_string()).join(x.to_string()).join(format!("{}.png", y)));
+    if let Some(png) = path.as_ref().and_then(|path| fs::read(path).ok()) {
+        return Ok(png);
+    }
+
+    let image = tiles::xyz_tile(project, zoom, x, y)?;
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    if let Some(path) = path {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside then renamed, so that a concurrent request never
+        // reads half a file
+        let partial = path.with_extension(format!("{}.part", PARTIAL.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&partial, &png)?;
+        fs::rename(&partial, &path)?;
+    }
+    Ok(png)
+}
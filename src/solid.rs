@@ -0,0 +1,227 @@
+//! Three-dimensional subdivision: each cube is cut into `grid³` cells and the
+//! cells of the keep mask are subdivided again, like the 2D [`crate::rule`].
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Cubes a mesh may have: at most 12 triangles each, so at most 600 MB of STL.
+pub const MAX_MESH_CUBES: u64 = 1 << 20;
+
+/// Serialized as `grid:mask`, the mask listing the cells layer by layer
+/// (`z`), then row by row (`y`): `3:101000101000000000101000101` is the 3D
+/// Cantor dust. `dust` and `menger` are accepted as names.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SolidRule {
+    pub grid: u32,
+    /// Keep mask, `grid³` entries, `x` fastest.
+    pub keep: Vec<bool>,
+}
+
+impl Default for SolidRule {
+    fn default() -> Self {
+        Self::menger()
+    }
+}
+
+impl SolidRule {
+    fn from_fn(grid: u32, keep: impl Fn(u32, u32, u32) -> bool) -> Self {
+        let keep = (0..grid)
+            .flat_map(|z| (0..grid).flat_map(move |y| (0..grid).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| keep(x, y, z))
+            .collect();
+        Self { grid, keep }
+    }
+
+    /// 3D Cantor dust: thirds, keeping the 8 corner cubes.
+    pub fn dust() -> Self {
+        Self::from_fn(3, |x, y, z| [x, y, z].iter().all(|&c| c != 1))
+    }
+
+    /// Menger sponge: thirds, removing the center and the 6 face centers.
+    pub fn menger() -> Self {
+        Self::from_fn(3, |x, y, z| [x, y, z].iter().filter(|&&c| c == 1).count() <= 1)
+    }
+
+    pub fn keeps(&self, x: u32, y: u32, z: u32) -> bool {
+        self.keep[((z * self.grid + y) * self.grid + x) as usize]
+    }
+
+    /// Kept cells, as `(x, y, z)` positions in the grid.
+    pub fn cells(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        let grid = self.grid;
+        (0..grid)
+            .flat_map(move |z| (0..grid).flat_map(move |y| (0..grid).map(move |x| [x, y, z])))
+            .filter(|&[x, y, z]| self.keeps(x, y, z))
+    }
+
+    pub fn kept(&self) -> u32 {
+        self.keep.iter().filter(|&&keep| keep).count() as u32
+    }
+
+    /// `log(kept) / log(grid)`: log 20 / log 3 ≈ 2.7268 for the Menger sponge.
+    pub fn dimension(&self) -> f64 {
+        match self.kept() {
+            0 => 0.0,
+            kept => (kept as f64).ln() / (self.grid as f64).ln(),
+        }
+    }
+
+    /// Cubes after `iterations` steps, as positions in a `grid^iterations`
+    /// voxel grid.
+    pub fn voxels(&self, iterations: u32) -> Voxels {
+        let resolution = self.grid.pow(iterations);
+        let mut voxels = Voxels::new(resolution);
+        let cells: Vec<[u32; 3]> = self.cells().collect();
+        let mut stack = vec![([0u32; 3], resolution)];
+        while let Some((corner, side)) = stack.pop() {
+            if side == 1 {
+                voxels.insert(corner);
+                continue;
+            }
+            let sub = side / self.grid;
+            for cell in &cells {
+                stack.push(([0, 1, 2].map(|axis| corner[axis] + cell[axis] * sub), sub));
+            }
+        }
+        voxels
+    }
+
+    /// Deepest iteration whose voxel grid has at most `max` cells per side.
+    pub fn depth_limit(&self, max: u32) -> u32 {
+        crate::rule::depth_limit(self.grid, max as f64)
+    }
+
+    /// Deepest iteration with at most `max` cubes: 4 for the Menger sponge
+    /// and 6 for the 3D dust under [`MAX_MESH_CUBES`].
+    pub fn cube_limit(&self, max: u64) -> u32 {
+        let kept = self.kept() as u64;
+        if kept <= 1 {
+            return u32::MAX;
+        }
+        let (mut depth, mut cubes) = (0, kept);
+        while cubes <= max {
+            depth += 1;
+            cubes = cubes.saturating_mul(kept);
+        }
+        depth
+    }
+}
+
+impl fmt::Display for SolidRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mask: String = self.keep.iter().map(|&keep| if keep { '1' } else { '0' }).collect();
+        write!(f, "{}:{}", self.grid, mask)
+    }
+}
+
+impl FromStr for SolidRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "dust" | "poussiere" | "poussière" => return Ok(Self::dust()),
+            "menger" | "eponge" | "éponge" => return Ok(Self::menger()),
+            _ => {}
+        }
+        let (grid, mask) = value
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("règle 3D invalide: {} (grille:masque, dust ou menger)", value))?;
+        let grid: u32 = grid.parse().map_err(|_| format!("grille invalide: {}", grid))?;
+        if !(2..=7).contains(&grid) {
+            return Err(format!("grille hors de 2..=7: {}", grid));
+        }
+        let keep = mask
+            .chars()
+            .map(|c| match c {
+                '1' => Ok(true),
+                '0' => Ok(false),
+                c => Err(format!("caractère invalide dans le masque: {}", c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if keep.len() != (grid * grid * grid) as usize {
+            return Err(format!("le masque doit avoir {} cases, pas {}", grid * grid * grid, keep.len()));
+        }
+        Ok(Self { grid, keep })
+    }
+}
+
+impl TryFrom<String> for SolidRule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SolidRule> for String {
+    fn from(rule: SolidRule) -> Self {
+        rule.to_string()
+    }
+}
+
+/// Occupied cells of a `resolution³` grid, one bit each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Voxels {
+    pub resolution: u32,
+    bits: Vec<u64>,
+    count: u64,
+}
+
+impl Voxels {
+    pub fn new(resolution: u32) -> Self {
+        let cells = (resolution as u64).pow(3);
+        Self {
+            resolution,
+            bits: vec![0; cells.div_ceil(64) as usize],
+            count: 0,
+        }
+    }
+
+    fn index(&self, [x, y, z]: [u32; 3]) -> usize {
+        let n = self.resolution as usize;
+        (z as usize * n + y as usize) * n + x as usize
+    }
+
+    pub fn insert(&mut self, cell: [u32; 3]) {
+        let index = self.index(cell);
+        let (word, bit) = (index / 64, 1u64 << (index % 64));
+        if self.bits[word] & bit == 0 {
+            self.bits[word] |= bit;
+            self.count += 1;
+        }
+    }
+
+    /// Whether `cell` is occupied; cells outside the grid are empty.
+    pub fn contains(&self, [x, y, z]: [i64; 3]) -> bool {
+        let n = self.resolution as i64;
+        if [x, y, z].iter().any(|&c| c < 0 || c >= n) {
+            return false;
+        }
+        let index = self.index([x as u32, y as u32, z as u32]);
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Occupied cells, `x` fastest.
+    pub fn iter(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        let n = self.resolution as usize;
+        self.bits.iter().enumerate().flat_map(move |(word, &bits)| {
+            (0..64)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| {
+                    let index = word * 64 + bit;
+                    [(index % n) as u32, (index / n % n) as u32, (index / (n * n)) as u32]
+                })
+        })
+    }
+}
@@ -0,0 +1,139 @@
+//! Theoretical measures of a construction and pixel counts of a render.
+
+use crate::affine::Maps;
+use crate::rule::Rule;
+use image::{Rgba, RgbaImage};
+
+/// Measures of the construction after `iterations` steps, for a unit square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measures {
+    pub iterations: u32,
+    /// Number of squares, `None` when it does not fit in 128 bits.
+    pub squares: Option<u128>,
+    pub side: f64,
+    pub area: f64,
+    pub perimeter: f64,
+    /// Hausdorff (similarity) dimension of the limit set.
+    pub dimension: f64,
+}
+
+impl Measures {
+    pub fn new(rule: &Rule, iterations: u32) -> Self {
+        let grid = rule.grid as f64;
+        let kept = rule.kept() as f64;
+
+        Self {
+            iterations,
+            squares: (rule.kept() as u128).checked_pow(iterations),
+            side: grid.powf(-(iterations as f64)),
+            area: (kept / (grid * grid)).powf(iterations as f64),
+            perimeter: perimeter(rule, iterations),
+            dimension: dimension(rule),
+        }
+    }
+
+    /// Measures of a multi-scale construction, assuming the images do not
+    /// overlap: `side` is the smallest square, and squares that touch still
+    /// count their shared edges in the perimeter.
+    pub fn from_maps(maps: &Maps, iterations: u32) -> Self {
+        let power = |value: f64| value.powf(iterations as f64);
+        let sum = |exponent| maps.0.iter().map(|map| map.ratio.powi(exponent)).sum::<f64>();
+        let smallest = maps.0.iter().map(|map| map.ratio).fold(1.0, f64::min);
+
+        Self {
+            iterations,
+            squares: (maps.0.len() as u128).checked_pow(iterations),
+            side: power(smallest),
+            area: power(sum(2)),
+            perimeter: 4.0 * power(sum(1)),
+            dimension: maps.dimension(),
+        }
+    }
+}
+
+/// `base^exponent` written in full when it fits in 128 bits, in scientific
+/// notation otherwise: `4^100 ≈ 1.607e60`.
+pub fn power_label(base: u32, exponent: u32) -> String {
+    match (base as u128).checked_pow(exponent) {
+        Some(value) => value.to_string(),
+        None => {
+            let log = exponent as f64 * (base as f64).log10();
+            let mantissa = 10f64.powf(log.fract());
+            format!("{}^{} ≈ {:.3}e{}", base, exponent, mantissa, log.trunc())
+        }
+    }
+}
+
+/// `log(kept) / log(grid)`: log 4 / log 3 ≈ 1.2619 for the Cantor square.
+pub fn dimension(rule: &Rule) -> f64 {
+    match rule.kept() {
+        0 => 0.0,
+        kept => (kept as f64).ln() / (rule.grid as f64).ln(),
+    }
+}
+
+/// Perimeter of the union of the squares, shared edges excluded.
+///
+/// Counts the pairs of neighbouring squares level by level: pairs inside one
+/// parent come from the mask, pairs across two neighbouring parents from the
+/// cells kept on both facing sides. Values are scaled by the side length so
+/// they stay finite for deep iterations.
+pub fn perimeter(rule: &Rule, iterations: u32) -> f64 {
+    let n = rule.grid;
+    let ratio = 1.0 / n as f64;
+
+    let inner_h = (0..n)
+        .flat_map(|y| (1..n).map(move |x| (x, y)))
+        .filter(|&(x, y)| rule.keeps(x - 1, y) && rule.keeps(x, y))
+        .count() as f64;
+    let inner_v = (1..n)
+        .flat_map(|y| (0..n).map(move |x| (x, y)))
+        .filter(|&(x, y)| rule.keeps(x, y - 1) && rule.keeps(x, y))
+        .count() as f64;
+    let across_h = (0..n).filter(|&y| rule.keeps(n - 1, y) && rule.keeps(0, y)).count() as f64;
+    let across_v = (0..n).filter(|&x| rule.keeps(x, n - 1) && rule.keeps(x, 0)).count() as f64;
+
+    // Squares and neighbour pairs, each multiplied by the current side. Far
+    // enough, the sums have converged, vanished or overflowed in f64.
+    let (mut squares, mut pairs_h, mut pairs_v) = (1.0_f64, 0.0, 0.0);
+    for _ in 0..iterations.min(100_000) {
+        if !squares.is_finite() {
+            break;
+        }
+        let next_squares = squares * rule.kept() as f64 * ratio;
+        pairs_h = (squares * inner_h + pairs_h * across_h) * ratio;
+        pairs_v = (squares * inner_v + pairs_v * across_v) * ratio;
+        squares = next_squares;
+    }
+
+    if squares.is_finite() {
+        4.0 * squares - 2.0 * (pairs_h + pairs_v)
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Pixel counts of an actual render.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelStats {
+    pub total: u64,
+    /// Pixels that differ from the background.
+    pub filled: u64,
+}
+
+impl PixelStats {
+    pub fn count(image: &RgbaImage, background: Rgba<u8>) -> Self {
+        Self {
+            total: image.width() as u64 * image.height() as u64,
+            filled: image.pixels().filter(|&&pixel| pixel != background).count() as u64,
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.filled as f64 / self.total as f64
+        }
+    }
+}
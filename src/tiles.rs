@@ -0,0 +1,432 @@
+//! Still views too large to hold in memory, such as 59049 × 59049 (3^10)
+//! prints. They are rendered a strip at a time with [`render::render_rgba`]
+//! and streamed to a PNG or a tiled BigTIFF, or cut into a Deep Zoom (DZI)
+//! or XYZ tile pyramid to browse offline.
+//!
+//! Each pyramid level is rendered again at its own size rather than
+//! downsampled from the level below: the fractal has detail at every scale.
+
+use crate::camera::Camera;
+use crate::dust::Dust;
+use crate::interval::Figure;
+use crate::metadata;
+use crate::project::Project;
+use crate::render::{self, Style};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use image::RgbaImage;
+use image::imageops;
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Side of the TIFF, DZI and XYZ tiles.
+pub const TILE: u32 = 256;
+/// Pixels rendered at once: 16 Mi pixels, 64 MiB of RGBA.
+const BUDGET: u64 = 16 << 20;
+/// Viewer of the XYZ pyramids, offline or from [`crate::server`].
+pub const VIEWER: &str = include_str!("viewer.html");
+
+/// Step of a long export, reported before it starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress {
+    /// Band of full rows of a streamed PNG, from 1.
+    Band { index: u32, count: u32 },
+    /// Row of tiles of a BigTIFF, from 1.
+    TileRow { index: u32, count: u32 },
+    /// Level of a DZI or XYZ pyramid, from 0 to `last`, `size` pixels wide.
+    Level { index: u32, last: u32, size: u64 },
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Progress::Band { index, count } => write!(f, "Bande {}/{}", index, count),
+            Progress::TileRow { index, count } => write!(f, "Ligne de tuiles {}/{}", index, count),
+            Progress::Level { index, last, size } => write!(f, "Niveau {}/{} ({} px)", index, last, size),
+        }
+    }
+}
+
+/// Renders any region of the `size × size` still view of a project.
+pub struct Tiler<'a> {
+    project: &'a Project,
+    size: u32,
+    camera: Camera,
+    style: Style,
+    dust: Option<Dust>,
+}
+
+impl<'a> Tiler<'a> {
+    /// Only the square figure can be tiled: the 1D figures are drawn as a
+    /// whole.
+    pub fn new(project: &'a Project, size: u32) -> Result<Self, Box<dyn Error>> {
+        if project.figure != Figure::Square {
+            return Err(format!("le rendu par tuiles ne gère que la figure carrée, pas {}", project.figure).into());
+        }
+        if size == 0 {
+            return Err("la taille de l'image doit être positive".into());
+        }
+        Ok(Self {
+            project,
+            size,
+            camera: project.still_camera(),
+            style: project.style(),
+            dust: project.dust(),
+        })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Pixels `x..x + width` and `y..y + height` of the still view. The
+    /// region may stick out of the image, the construction goes on there.
+    pub fn region(&self, x: u32, y: u32, width: u32, height: u32) -> RgbaImage {
+        let camera = region_camera(&self.camera, self.size as f64, [x as f64, y as f64], width, height);
+        render_region(self.project, &camera, width, height, self.dust.as_ref(), &self.style)
+    }
+
+    /// Tiles per side.
+    pub fn tiles(&self) -> u32 {
+        self.size.div_ceil(TILE)
+    }
+
+    /// Calls `visit(row, first_column, tiles)` with the tiles of each row, left
+    /// to right, several at a time. Edge tiles are cut to the image.
+    fn tile_rows(
+        &self,
+        mut visit: impl FnMut(u32, u32, Vec<RgbaImage>) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let count = self.tiles();
+        let per_strip = (BUDGET / (TILE as u64 * TILE as u64)).clamp(1, count as u64) as u32;
+        for row in 0..count {
+            let y = row * TILE;
+            let height = TILE.min(self.size - y);
+            for first in (0..count).step_by(per_strip as usize) {
+                let x = first * TILE;
+                let width = (per_strip * TILE).min(self.size - x);
+                let strip = self.region(x, y, width, height);
+                let tiles = (0..width.div_ceil(TILE))
+                    .map(|i| imageops::crop_imm(&strip, i * TILE, 0, TILE.min(width - i * TILE), height).to_image())
+                    .collect();
+                visit(row, first, tiles)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Streams the image to a PNG, a band of full rows at a time.
+    pub fn save_png(
+        &self,
+        path: impl AsRef<Path>,
+        entries: &[(String, String)],
+        mut progress: impl FnMut(Progress),
+    ) -> Result<(), Box<dyn Error>> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.size, self.size);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        metadata::add_png_text(&mut encoder, entries)?;
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+
+        let rows = (BUDGET / self.size as u64).clamp(1, self.size as u64) as u32;
+        let bands = self.size.div_ceil(rows);
+        for band in 0..bands {
+            progress(Progress::Band { index: band + 1, count: bands });
+            let y = band * rows;
+            stream.write_all(self.region(0, y, self.size, rows.min(self.size - y)).as_raw())?;
+        }
+        stream.finish()?;
+        Ok(())
+    }
+
+    /// Writes a BigTIFF of deflated `TILE × TILE` tiles. The tiles come first
+    /// and the directory last, once their offsets are known.
+    pub fn save_tiff(
+        &self,
+        path: impl AsRef<Path>,
+        entries: &[(String, String)],
+        mut progress: impl FnMut(Progress),
+    ) -> Result<(), Box<dyn Error>> {
+        let mut file = BufWriter::new(File::create(path)?);
+        // « II », version 43, 8-byte offsets, then the directory offset
+        file.write_all(&[b'I', b'I', 43, 0, 8, 0, 0, 0])?;
+        file.write_all(&0u64.to_le_bytes())?;
+        let mut position = 16u64;
+
+        let count = self.tiles();
+        let mut offsets = Vec::with_capacity((count * count) as usize);
+        let mut lengths = Vec::with_capacity((count * count) as usize);
+        self.tile_rows(|row, _, tiles| {
+            progress(Progress::TileRow { index: row + 1, count });
+            let compressed = tiles
+                .into_par_iter()
+                .map(|tile| {
+                    // Edge tiles are padded to the full size
+                    let mut full = RgbaImage::new(TILE, TILE);
+                    imageops::replace(&mut full, &tile, 0, 0);
+                    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(full.as_raw())?;
+                    encoder.finish()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for data in compressed {
+                file.write_all(&data)?;
+                offsets.push(position);
+                lengths.push(data.len() as u64);
+                position += data.len() as u64;
+            }
+            Ok(())
+        })?;
+
+        let mut directory = TiffDirectory::default();
+        directory.long(256, self.size);
+        directory.long(257, self.size);
+        directory.shorts(258, &[8, 8, 8, 8]);
+        // Adobe deflate
+        directory.shorts(259, &[8]);
+        // RGB
+        directory.shorts(262, &[2]);
+        directory.ascii(270, &metadata::to_text(entries));
+        directory.shorts(277, &[4]);
+        directory.shorts(284, &[1]);
+        directory.ascii(305, "FraCantor");
+        directory.long(322, TILE);
+        directory.long(323, TILE);
+        directory.long8s(324, &offsets);
+        directory.long8s(325, &lengths);
+        // Unassociated alpha
+        directory.shorts(338, &[2]);
+        let start = directory.write(&mut file, position)?;
+        file.seek(SeekFrom::Start(8))?;
+        file.write_all(&start.to_le_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes each tile as a PNG at `name(column, row)`.
+    fn save_tiles(&self, name: impl Fn(u32, u32) -> std::path::PathBuf + Sync) -> Result<(), Box<dyn Error>> {
+        self.tile_rows(|row, first, tiles| {
+            tiles.par_iter().enumerate().try_for_each(|(i, tile)| {
+                let path = name(first + i as u32, row);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                tile.save(path)
+            })?;
+            Ok(())
+        })
+    }
+}
+
+/// Camera of the `width × height` region at pixel `corner` of a `size`-pixel
+/// drawing of the view of `still`.
+fn region_camera(still: &Camera, size: f64, corner: [f64; 2], width: u32, height: u32) -> Camera {
+    let pixel = 1.0 / (still.zoom * size);
+    let left = still.center[0] - 0.5 / still.zoom;
+    let top = still.center[1] - 0.5 / still.zoom;
+    Camera {
+        center: [
+            left + (corner[0] + width as f64 / 2.0) * pixel,
+            top + (corner[1] + height as f64 / 2.0) * pixel,
+        ],
+        zoom: still.zoom * size / width as f64,
+        rotation: 0.0,
+    }
+}
+
+fn render_region(
+    project: &Project,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    dust: Option<&Dust>,
+    style: &Style,
+) -> RgbaImage {
+    render::render_rgba(
+        camera,
+        width as usize,
+        height as usize,
+        &project.rule,
+        dust,
+        &project.maps,
+        project.iterations,
+        style,
+    )
+}
+
+/// Tile `(x, y)` of XYZ level `zoom`, at any depth: unlike [`Tiler`], the
+/// side of the level, `TILE · 2^zoom`, does not have to fit in 32 bits.
+/// Deep levels only gain detail if `project.iterations` goes that far, see
+/// [`Project::pixel_depth`].
+pub fn xyz_tile(project: &Project, zoom: u32, x: u64, y: u64) -> Result<RgbaImage, Box<dyn Error>> {
+    if project.figure != Figure::Square {
+        return Err(format!("le rendu par tuiles ne gère que la figure carrée, pas {}", project.figure).into());
+    }
+    if zoom > 48 {
+        return Err(format!("niveau de zoom trop grand: {} (48 au plus)", zoom).into());
+    }
+    let count = 1u64 << zoom;
+    if x >= count || y >= count {
+        return Err(format!("tuile hors du niveau {}: {}/{}", zoom, x, y).into());
+    }
+    let size = TILE as f64 * count as f64;
+    let corner = [x as f64 * TILE as f64, y as f64 * TILE as f64];
+    let camera = region_camera(&project.still_camera(), size, corner, TILE, TILE);
+    Ok(render_region(project, &camera, TILE, TILE, project.dust().as_ref(), &project.style()))
+}
+
+/// Entries of a BigTIFF image directory, with the values that do not fit in
+/// their 8 bytes kept aside.
+#[derive(Default)]
+struct TiffDirectory {
+    /// Tag, type, count and value or data.
+    entries: Vec<(u16, u16, u64, Vec<u8>)>,
+}
+
+impl TiffDirectory {
+    fn shorts(&mut self, tag: u16, values: &[u16]) {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.entries.push((tag, 3, values.len() as u64, data));
+    }
+
+    fn long(&mut self, tag: u16, value: u32) {
+        self.entries.push((tag, 4, 1, value.to_le_bytes().to_vec()));
+    }
+
+    fn long8s(&mut self, tag: u16, values: &[u64]) {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.entries.push((tag, 16, values.len() as u64, data));
+    }
+
+    fn ascii(&mut self, tag: u16, text: &str) {
+        let mut data = text.as_bytes().to_vec();
+        data.push(0);
+        self.entries.push((tag, 2, data.len() as u64, data));
+    }
+
+    /// Writes the large values then the directory, from `position` in the
+    /// file, and returns the offset of the directory.
+    fn write(mut self, out: &mut impl Write, mut position: u64) -> std::io::Result<u64> {
+        self.entries.sort_by_key(|entry| entry.0);
+        let mut values = Vec::with_capacity(self.entries.len());
+        for (_, _, _, data) in &self.entries {
+            if data.len() > 8 {
+                out.write_all(data)?;
+                values.push(position.to_le_bytes());
+                position += data.len() as u64;
+            } else {
+                let mut value = [0; 8];
+                value[..data.len()].copy_from_slice(data);
+                values.push(value);
+            }
+        }
+        // Directories start on a word boundary
+        if position % 2 == 1 {
+            out.write_all(&[0])?;
+            position += 1;
+        }
+
+        out.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for ((tag, kind, count, _), value) in self.entries.iter().zip(&values) {
+            out.write_all(&tag.to_le_bytes())?;
+            out.write_all(&kind.to_le_bytes())?;
+            out.write_all(&count.to_le_bytes())?;
+            out.write_all(value)?;
+        }
+        out.write_all(&0u64.to_le_bytes())?;
+        Ok(position)
+    }
+}
+
+/// Renders the still view of `project` at `size × size` into `path`: a
+/// streamed PNG, a tiled BigTIFF (`tif`, `tiff`) or a Deep Zoom pyramid
+/// (`dzi`), calling `progress` before each step.
+pub fn export(project: &Project, size: u32, path: &str, progress: impl FnMut(Progress)) -> Result<(), Box<dyn Error>> {
+    let entries = project.metadata();
+    match metadata::extension(Path::new(path)).as_deref() {
+        Some("png") => Tiler::new(project, size)?.save_png(path, &entries, progress),
+        Some("tif") | Some("tiff") => Tiler::new(project, size)?.save_tiff(path, &entries, progress),
+        Some("dzi") => export_dzi(project, size, path, progress),
+        _ => Err(format!("format de rendu par tuiles non reconnu: {} (png, tif, tiff, dzi)", path).into()),
+    }
+}
+
+/// Deep Zoom pyramid: `name.dzi` and the tiles in `name_files/{level}/`,
+/// from one pixel at level 0 to `size` at the last level, as read by
+/// OpenSeadragon.
+pub fn export_dzi(project: &Project, size: u32, path: &str, mut progress: impl FnMut(Progress)) -> Result<(), Box<dyn Error>> {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("cantor");
+    let files = path.with_file_name(format!("{}_files", stem));
+    let levels = size.max(1).next_power_of_two().trailing_zeros();
+
+    for level in 0..=levels {
+        let level_size = size.div_ceil(1 << (levels - level));
+        progress(Progress::Level {
+            index: level,
+            last: levels,
+            size: level_size as u64,
+        });
+        let tiler = Tiler::new(project, level_size)?;
+        let dir = files.join(level.to_string());
+        tiler.save_tiles(|column, row| dir.join(format!("{}_{}.png", column, row)))?;
+    }
+
+    fs::write(
+        path,
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"png\" Overlap=\"0\" TileSize=\"{}\">\n  \
+             <Size Width=\"{}\" Height=\"{}\"/>\n\
+             </Image>\n",
+            TILE, size, size
+        ),
+    )?;
+    Ok(())
+}
+
+/// Zoom levels of an XYZ pyramid whose last level is at least `size` pixels.
+pub fn xyz_levels(size: u32) -> u32 {
+    size.div_ceil(TILE).max(1).next_power_of_two().trailing_zeros()
+}
+
+/// XYZ pyramid: `dir/{z}/{x}/{y}.png` for `z` in `0..=max_zoom`, level `z`
+/// being the still view at `TILE · 2^z` pixels, with an `index.html` viewer.
+pub fn export_xyz(
+    project: &Project,
+    max_zoom: u32,
+    dir: impl AsRef<Path>,
+    mut progress: impl FnMut(Progress),
+) -> Result<(), Box<dyn Error>> {
+    let dir = dir.as_ref();
+    if max_zoom > 14 {
+        return Err(format!("niveau de zoom trop grand: {} (14 au plus)", max_zoom).into());
+    }
+    for zoom in 0..=max_zoom {
+        progress(Progress::Level {
+            index: zoom,
+            last: max_zoom,
+            size: (TILE as u64) << zoom,
+        });
+        let tiler = Tiler::new(project, TILE << zoom)?;
+        let level = dir.join(zoom.to_string());
+        tiler.save_tiles(|x, y| level.join(x.to_string()).join(format!("{}.png", y)))?;
+    }
+    fs::write(dir.join("index.html"), viewer("{z}/{x}/{y}.png", max_zoom))?;
+    Ok(())
+}
+
+/// The viewer page, reading the tiles from `tiles` (`{z}`, `{x}` and `{y}`
+/// replaced).
+pub fn viewer(tiles: &str, max_zoom: u32) -> String {
+    VIEWER
+        .replace("{{TITLE}}", "FraCantor")
+        .replace("{{TILES}}", tiles)
+        .replace("{{MAX_ZOOM}}", &max_zoom.to_string())
+}
@@ -0,0 +1,117 @@
+//! Frame timing: per-frame delays and loop count of the animations.
+
+use crate::camera::Easing;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timing {
+    /// Delay of a regular frame in milliseconds.
+    pub delay_ms: f64,
+    /// Extra time on the first frame, in milliseconds.
+    pub hold_first_ms: f64,
+    /// Extra time on the last frame, in milliseconds.
+    pub hold_last_ms: f64,
+    /// How frame times are spread over the animation: `linear` keeps a
+    /// constant rate, the ease curves slow down the start and/or the end.
+    pub easing: Easing,
+    /// Number of plays, 0 for an infinite loop.
+    pub plays: u32,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            delay_ms: 40.0, // 25 fps
+            hold_first_ms: 0.0,
+            hold_last_ms: 0.0,
+            easing: Easing::Linear,
+            plays: 0,
+        }
+    }
+}
+
+impl Timing {
+    /// Delays in milliseconds of each of `count` frames.
+    ///
+    /// Frame `i` starts when the eased progress of the animation reaches
+    /// `i / count`, so the total duration stays `count × delay_ms` (plus holds).
+    pub fn delays(&self, count: u32) -> Vec<f64> {
+        let total = self.delay_ms * count as f64;
+        let start = |i: u32| total * self.inverse_easing(i as f64 / count as f64);
+
+        let mut delays: Vec<f64> = (0..count).map(|i| start(i + 1) - start(i)).collect();
+        if let Some(first) = delays.first_mut() {
+            *first += self.hold_first_ms;
+        }
+        if let Some(last) = delays.last_mut() {
+            *last += self.hold_last_ms;
+        }
+        delays
+    }
+
+    /// Time fraction at which the eased progress reaches `progress`.
+    fn inverse_easing(&self, progress: f64) -> f64 {
+        if self.easing == Easing::Linear {
+            return progress;
+        }
+        // Easing curves are monotonic: bisection is enough
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..50 {
+            let mid = (low + high) / 2.0;
+            if self.easing.apply(mid) < progress {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        high
+    }
+}
+
+/// Parses a frame delay written `40`, `40ms` or `25fps`, in milliseconds.
+pub fn parse_delay(value: &str) -> Result<f64, String> {
+    let value = value.trim();
+    let invalid = || format!("délai invalide: {} (ex: 40, 40ms, 25fps)", value);
+
+    let delay = if let Some(fps) = value.strip_suffix("fps") {
+        let fps: f64 = fps.trim().parse().map_err(|_| invalid())?;
+        if fps <= 0.0 {
+            return Err(invalid());
+        }
+        1000.0 / fps
+    } else {
+        value
+            .strip_suffix("ms")
+            .unwrap_or(value)
+            .trim()
+            .parse()
+            .map_err(|_| invalid())?
+    };
+
+    if delay.is_finite() && delay >= 0.0 {
+        Ok(delay)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Converts delays in milliseconds to whole ticks of a coarser unit (GIF
+/// centiseconds, video frames…) by rounding the running total, so that
+/// rounding errors never add up: 30 fps in centiseconds gives 3, 4, 3, …
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ticks {
+    elapsed_ms: f64,
+    emitted: u64,
+}
+
+impl Ticks {
+    /// Number of `tick_ms` ticks that the next frame, shown for `delay_ms`, lasts.
+    pub fn advance(&mut self, delay_ms: f64, tick_ms: f64) -> u64 {
+        self.elapsed_ms += delay_ms;
+        let total = (self.elapsed_ms / tick_ms).round() as u64;
+        let ticks = total.saturating_sub(self.emitted);
+        self.emitted = total.max(self.emitted);
+        ticks
+    }
+}
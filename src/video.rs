@@ -0,0 +1,170 @@
+//! MP4/WebM output through a local `ffmpeg` process.
+//!
+//! Raw RGBA frames are piped to ffmpeg's stdin. When ffmpeg is not installed,
+//! the frames are written as a numbered PNG sequence instead.
+
+use crate::animation::FrameWriter;
+use crate::timing::Ticks;
+use image::RgbaImage;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+#[derive(Clone, Copy, Debug)]
+pub struct VideoOptions {
+    pub fps: u32,
+    /// Constant rate factor: lower is better (0–51 for H.264, 0–63 for VP9).
+    pub quality: u32,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self { fps: 25, quality: 23 }
+    }
+}
+
+impl VideoOptions {
+    fn frame_ms(&self) -> f64 {
+        1000.0 / self.fps as f64
+    }
+}
+
+pub fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Directory of the PNG sequence written instead of the video `path`.
+pub fn fallback_directory(path: &str) -> PathBuf {
+    Path::new(path).with_extension("frames")
+}
+
+/// Message to show before encoding `path` when it is a video and ffmpeg is
+/// missing, `None` otherwise.
+pub fn fallback_notice(path: &str) -> Option<String> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    (matches!(extension.as_str(), "mp4" | "webm") && !ffmpeg_available()).then(|| {
+        format!(
+            "ffmpeg introuvable: les frames seront enregistrées en PNG dans {}",
+            fallback_directory(path).display()
+        )
+    })
+}
+
+/// Opens an encoder for `path`, or a PNG sequence in
+/// [`fallback_directory`] when ffmpeg is missing.
+pub fn create(
+    path: &str,
+    width: u32,
+    height: u32,
+    options: &VideoOptions,
+) -> Result<Box<dyn FrameWriter>, Box<dyn Error>> {
+    if ffmpeg_available() {
+        return Ok(Box::new(FfmpegWriter::spawn(path, width, height, options)?));
+    }
+    Ok(Box::new(PngSequence::create(fallback_directory(path), *options)?))
+}
+
+/// Frames are repeated or dropped to follow their delays at the video rate.
+pub struct FfmpegWriter {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    options: VideoOptions,
+    ticks: Ticks,
+}
+
+impl FfmpegWriter {
+    pub fn spawn(path: &str, width: u32, height: u32, options: &VideoOptions) -> Result<Self, Box<dyn Error>> {
+        let size = format!("{}x{}", width, height);
+        let fps = options.fps.to_string();
+        let quality = options.quality.to_string();
+
+        // VP9 keeps the alpha channel, H.264 does not
+        let (codec, pix_fmt): (&[&str], &str) = if path.to_ascii_lowercase().ends_with(".webm") {
+            (&["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", &quality], "yuva420p")
+        } else {
+            (&["-c:v", "libx264", "-preset", "medium", "-crf", &quality, "-movflags", "+faststart"], "yuv420p")
+        };
+
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-s", &size, "-r", &fps, "-i", "-"])
+            .args(codec)
+            // 4:2:0 chroma needs even dimensions
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", pix_fmt])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+
+        Ok(Self {
+            child,
+            stdin,
+            options: *options,
+            ticks: Ticks::default(),
+        })
+    }
+}
+
+impl FrameWriter for FfmpegWriter {
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>> {
+        let stdin = self.stdin.as_mut().ok_or("ffmpeg est déjà fermé")?;
+        for _ in 0..self.ticks.advance(delay_ms, self.options.frame_ms()) {
+            stdin.write_all(frame.as_raw())?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        // Closing stdin tells ffmpeg that the stream is over
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(format!("ffmpeg a échoué ({})", status).into());
+        }
+        Ok(())
+    }
+}
+
+/// Fallback writer: `frame_00000.png`, `frame_00001.png`, … in a directory.
+pub struct PngSequence {
+    directory: PathBuf,
+    options: VideoOptions,
+    ticks: Ticks,
+    next: u32,
+}
+
+impl PngSequence {
+    pub fn create(directory: impl Into<PathBuf>, options: VideoOptions) -> Result<Self, Box<dyn Error>> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            options,
+            ticks: Ticks::default(),
+            next: 0,
+        })
+    }
+}
+
+impl FrameWriter for PngSequence {
+    fn write_frame(&mut self, frame: &RgbaImage, delay_ms: f64) -> Result<(), Box<dyn Error>> {
+        // Same frame count as the video would have, so the sequence can be
+        // encoded later at the same frame rate
+        for _ in 0..self.ticks.advance(delay_ms, self.options.frame_ms()) {
+            frame.save(self.directory.join(format!("frame_{:05}.png", self.next)))?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
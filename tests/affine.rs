@@ -0,0 +1,31 @@
+use fractal_generation_rust::affine::{Map, Maps};
+
+fn maps(ratios: &[f64]) -> Maps {
+    // Offsets do not enter the similarity dimension
+    Maps(ratios.iter().map(|&ratio| Map { ratio, offset: [0.0, 0.0] }).collect())
+}
+
+#[test]
+fn dimension_solves_the_moran_equation() {
+    // 1/2 + 2 · 1/4 = 1
+    assert!((maps(&[0.5, 0.25, 0.25]).dimension() - 1.0).abs() < 1e-12);
+    let uniform = maps(&[1.0 / 3.0; 5]);
+    assert!((uniform.dimension() - 5f64.ln() / 3f64.ln()).abs() < 1e-12);
+    // 4 halves fill the square
+    assert!((maps(&[0.5; 4]).dimension() - 2.0).abs() < 1e-12);
+    // u + 3u² = 1 with u = (1/2)^s
+    let u = (13f64.sqrt() - 1.0) / 6.0;
+    assert!((Maps::example().dimension() - u.ln() / 0.5f64.ln()).abs() < 1e-12);
+
+    assert_eq!(maps(&[]).dimension(), 0.0);
+    assert_eq!(maps(&[0.5]).dimension(), 0.0);
+}
+
+#[test]
+fn validate_keeps_the_images_in_the_unit_square() {
+    assert_eq!(Maps::example().validate(), Ok(()));
+    for map in ["1@0,0", "0@0,0", "0.5@0.6,0", "0.5@0,-0.1"] {
+        let maps = Maps(vec![map.parse().unwrap()]);
+        assert!(maps.validate().is_err(), "{}", map);
+    }
+}
@@ -0,0 +1,36 @@
+use fractal_generation_rust::box_counting;
+use fractal_generation_rust::rule::Rule;
+use image::{Rgba, RgbaImage};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+fn cantor(iterations: u32, size: u32) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(size, size, WHITE);
+    Rule::cantor().draw(&mut image, 0, 0, size, iterations, Rgba([0, 0, 0, 255]));
+    image
+}
+
+#[test]
+fn construction_sizes_start_at_the_last_squares() {
+    assert_eq!(box_counting::construction_sizes(243, 3, 5), vec![1, 3, 9, 27]);
+    assert_eq!(box_counting::construction_sizes(729, 3, 4), vec![9, 27, 81]);
+    // Iterations past the resolution stop at one-pixel squares
+    assert_eq!(box_counting::construction_sizes(81, 3, 18), vec![1, 3, 9]);
+}
+
+#[test]
+fn cantor_dust_dimension_is_log4_over_log3() {
+    let expected = 4f64.ln() / 3f64.ln();
+    for (iterations, size) in [(5, 243), (4, 729), (6, 729)] {
+        let sizes = box_counting::construction_sizes(size, 3, iterations);
+        let estimate = box_counting::estimate(&cantor(iterations, size), WHITE, &sizes);
+        assert!(
+            (estimate.dimension - expected).abs() < 1e-3,
+            "{} itérations sur {} px: {}",
+            iterations,
+            size,
+            estimate.dimension
+        );
+        assert!(estimate.r_squared > 0.999);
+    }
+}
@@ -0,0 +1,30 @@
+use fractal_generation_rust::camera::{CameraPath, Easing, Keyframe};
+
+fn keyframe(time: f64, zoom: f64) -> Keyframe {
+    Keyframe {
+        time,
+        center: [0.25, 0.25],
+        zoom,
+        rotation: 0.0,
+        easing: Easing::Linear,
+    }
+}
+
+#[test]
+fn unsorted_keyframes_sample_like_sorted_ones() {
+    let sorted = CameraPath::new(vec![keyframe(0.0, 1.0), keyframe(1.0, 9.0), keyframe(2.0, 81.0)]);
+    let mut edited = sorted.clone();
+    edited.keyframes.swap(0, 2);
+    for time in [-1.0, 0.0, 0.5, 1.0, 1.5, 2.0, 3.0] {
+        assert_eq!(edited.sample(time), sorted.sample(time), "temps {}", time);
+    }
+    assert_eq!((edited.start_time(), edited.end_time()), (0.0, 2.0));
+}
+
+#[test]
+fn empty_or_non_finite_paths_are_rejected() {
+    assert!(CameraPath::default().validate().is_err());
+    assert!(CameraPath::new(vec![keyframe(f64::NAN, 1.0)]).validate().is_err());
+    assert!(CameraPath::new(vec![keyframe(0.0, 0.0)]).validate().is_err());
+    assert!(CameraPath::new(vec![keyframe(0.0, 1.0)]).validate().is_ok());
+}
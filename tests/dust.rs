@@ -0,0 +1,99 @@
+use fractal_generation_rust::affine::Maps;
+use fractal_generation_rust::camera::Camera;
+use fractal_generation_rust::dust::{Dust, Selection};
+use fractal_generation_rust::render::{self, Coloring, Style};
+use fractal_generation_rust::rule::Rule;
+
+const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+const FILL: [u8; 4] = [0, 0, 0, 255];
+
+#[test]
+fn frames_draw_the_same_squares_as_the_recursive_walk() {
+    let size = 81;
+    for selection in [Selection::Probability(0.6), Selection::Pick(4)] {
+        let dust = Dust { grid: 3, selection, seed: 7 };
+        let rule = Rule {
+            grid: 3,
+            keep: vec![true; 9],
+        };
+        let style = Style {
+            background: BACKGROUND,
+            fill: FILL,
+            border: None,
+            coloring: Coloring::Uniform,
+            palette: Vec::new(),
+        };
+        let camera = Camera::default();
+        let frame = render::render_rgba(&camera, size, size, &rule, Some(&dust), &Maps::default(), 4, &style);
+
+        let mut filled = vec![false; size * size];
+        dust.squares(0, 0, size as u32, 4, &mut |x, y, side, _| {
+            for j in y..y + side {
+                for i in x..x + side {
+                    filled[j as usize * size + i as usize] = true;
+                }
+            }
+        });
+        for (index, pixel) in frame.pixels().enumerate() {
+            let expected = if filled[index] { FILL } else { BACKGROUND };
+            assert_eq!(pixel.0, expected, "{} pixel {}", selection, index);
+        }
+    }
+}
+
+#[test]
+fn a_seed_always_draws_the_same_masks() {
+    let dust = Dust {
+        grid: 4,
+        selection: Selection::Probability(0.5),
+        seed: 2024,
+    };
+    for level in 0..4 {
+        for cell in [[0, 0], [3, 1], [17, 42]] {
+            assert_eq!(dust.mask(level, cell), dust.mask(level, cell));
+        }
+    }
+    assert_eq!(dust.count(729, 5), dust.count(729, 5));
+
+    let other = Dust { seed: 2025, ..dust };
+    let masks = |dust: &Dust| (0..8).map(|x| dust.mask(1, [x, 0])).collect::<Vec<_>>();
+    assert_ne!(masks(&dust), masks(&other));
+}
+
+#[test]
+fn picking_k_cells_keeps_exactly_k_per_square() {
+    let dust = Dust {
+        grid: 3,
+        selection: Selection::Pick(4),
+        seed: 11,
+    };
+    for cell in [[0, 0], [1, 2], [5, 7]] {
+        assert_eq!(dust.mask(2, cell).iter().filter(|&&keep| keep).count(), 4);
+    }
+    assert_eq!(dust.count(729, 4), 4u64.pow(4));
+    assert_eq!(dust.expected_squares(4), 256.0);
+    assert!((dust.expected_dimension() - 4f64.ln() / 3f64.ln()).abs() < 1e-12);
+}
+
+#[test]
+fn percolation_counts_average_to_the_expected_squares() {
+    let (iterations, runs) = (3, 400);
+    let model = Dust {
+        grid: 3,
+        selection: Selection::Probability(0.6),
+        seed: 0,
+    };
+    let total: u64 = (0..runs).map(|seed| Dust { seed, ..model }.count(729, iterations)).sum();
+    let mean = total as f64 / runs as f64;
+    let expected = model.expected_squares(iterations);
+    assert!((expected - 5.4f64.powi(3)).abs() < 1e-9);
+    assert!((mean - expected).abs() < 0.05 * expected, "moyenne {} pour {}", mean, expected);
+
+    assert!((model.expected_dimension() - 5.4f64.ln() / 3f64.ln()).abs() < 1e-12);
+    // p grid² ≤ 1: the dust dies out
+    let sparse = Dust {
+        selection: Selection::Probability(0.1),
+        ..model
+    };
+    assert_eq!(sparse.expected_dimension(), 0.0);
+}
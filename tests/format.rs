@@ -0,0 +1,52 @@
+use fractal_generation_rust::format::{self, ImageOptions};
+use fractal_generation_rust::metadata;
+use image::{ColorType, GenericImageView, Rgba, RgbaImage};
+use std::fs;
+
+fn gradient() -> RgbaImage {
+    RgbaImage::from_fn(37, 23, |x, y| Rgba([(x * 7) as u8, (y * 11) as u8, (x * y) as u8, 255]))
+}
+
+#[test]
+fn sixteen_bit_tiff_reads_back() {
+    let dir = std::env::temp_dir().join(format!("fracantor-format-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let image = gradient();
+    for (alpha, color) in [(true, ColorType::Rgba16), (false, ColorType::Rgb16)] {
+        let path = dir.join(format!("gradient-{}.tif", alpha));
+        let options = ImageOptions {
+            sixteen_bit: true,
+            alpha,
+            ..ImageOptions::default()
+        };
+        format::save(&image, &path, &[], &options).unwrap();
+        let read = image::open(&path).unwrap();
+        assert_eq!(read.color(), color);
+        let wide = read.to_rgba16();
+        for (pixel, wide) in image.pixels().zip(wide.pixels()) {
+            assert_eq!(pixel.0.map(|c| c as u16 * 257), wide.0);
+        }
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn jpeg_comment_follows_the_jfif_header() {
+    let dir = std::env::temp_dir().join(format!("fracantor-jpeg-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("gradient.jpg");
+    let mut entries = metadata::generator();
+    entries.push(metadata::entry("iterations", 4));
+    format::save(&gradient(), &path, &entries, &ImageOptions::default()).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    // SOI, then APP0 "JFIF"
+    assert_eq!(bytes[..4], [0xFF, 0xD8, 0xFF, 0xE0]);
+    assert_eq!(&bytes[6..11], b"JFIF\0");
+    let app0 = 4 + u16::from_be_bytes([bytes[4], bytes[5]]) as usize;
+    assert_eq!(bytes[app0..app0 + 2], [0xFF, 0xFE]);
+
+    assert_eq!(metadata::read(&path).unwrap(), entries);
+    assert_eq!(image::open(&path).unwrap().dimensions(), (37, 23));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,58 @@
+use fractal_generation_rust::history::History;
+
+#[test]
+fn identical_states_are_not_recorded() {
+    let mut history = History::new(1, 10);
+    assert!(!history.record(&1, "rien"));
+    assert!(!history.can_undo());
+    assert!(history.record(&2, "deux"));
+    assert!(!history.record(&2, "encore deux"));
+    assert_eq!(history.done().collect::<Vec<_>>(), ["deux"]);
+}
+
+#[test]
+fn undo_and_redo_walk_the_changes() {
+    let mut history = History::new(0, 10);
+    for state in 1..=3 {
+        history.record(&state, format!("état {}", state));
+    }
+    assert_eq!(history.undo(), Some(&2));
+    assert_eq!(history.undo(), Some(&1));
+    assert_eq!(history.done().collect::<Vec<_>>(), ["état 1"]);
+    assert_eq!(history.undone().collect::<Vec<_>>(), ["état 2", "état 3"]);
+    assert_eq!(history.redo(), Some(&2));
+    assert_eq!(history.current(), &2);
+    assert_eq!(history.undone().collect::<Vec<_>>(), ["état 3"]);
+}
+
+#[test]
+fn a_new_change_clears_the_redo() {
+    let mut history = History::new(0, 10);
+    history.record(&1, "un");
+    history.record(&2, "deux");
+    history.undo();
+    assert!(history.can_redo());
+    history.record(&5, "cinq");
+    assert!(!history.can_redo());
+    assert_eq!(history.redo(), None);
+    assert_eq!(history.done().collect::<Vec<_>>(), ["un", "cinq"]);
+}
+
+#[test]
+fn only_the_last_changes_are_kept() {
+    let mut history = History::new(0, 3);
+    for state in 1..=5 {
+        history.record(&state, state.to_string());
+    }
+    assert_eq!(history.done().collect::<Vec<_>>(), ["3", "4", "5"]);
+    while history.undo().is_some() {}
+    assert_eq!(history.current(), &2);
+    assert_eq!(history.undone().count(), 3);
+
+    // A limit of 0 still keeps one change
+    let mut history = History::new(0, 0);
+    history.record(&1, "un");
+    history.record(&2, "deux");
+    assert_eq!(history.undo(), Some(&1));
+    assert_eq!(history.undo(), None);
+}
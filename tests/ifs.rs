@@ -0,0 +1,103 @@
+use fractal_generation_rust::ifs::{self, Histogram, Ifs, Transform};
+
+const SIZE: u32 = 200;
+
+fn sierpinski() -> Ifs {
+    ifs::named("sierpinski").unwrap()
+}
+
+/// Hits in the `5 × 5` pixels around `(x, y)`.
+fn around(histogram: &Histogram, [x, y]: [u32; 2]) -> u32 {
+    (y - 2..=y + 2)
+        .flat_map(|py| (x - 2..=x + 2).map(move |px| histogram.counts[(py * histogram.width + px) as usize]))
+        .sum()
+}
+
+#[test]
+fn validate_rejects_unusable_systems() {
+    for system in ifs::gallery() {
+        assert_eq!(system.validate(), Ok(()), "{}", system.name);
+    }
+
+    let with = |maps: Vec<Transform>| Ifs {
+        name: String::new(),
+        maps,
+    };
+    let half = Transform::scaling(0.5, [0.0, 0.0]);
+    let weighted = |probability| Transform {
+        probability: Some(probability),
+        ..half
+    };
+    let invalid = [
+        with(Vec::new()),
+        with(vec![half, Transform::scaling(1.0, [0.5, 0.0])]),
+        with(vec![Transform::scaling(f64::NAN, [0.0, 0.0])]),
+        with(vec![half, Transform::scaling(0.5, [f64::INFINITY, 0.0])]),
+        with(vec![weighted(-0.5)]),
+        with(vec![weighted(0.0), weighted(0.0)]),
+    ];
+    for system in invalid {
+        assert!(system.validate().is_err(), "{:?}", system.maps);
+    }
+}
+
+#[test]
+fn bounds_hold_the_fixed_points() {
+    for system in ifs::gallery() {
+        let [min_x, min_y, max_x, max_y] = system.bounds();
+        for map in &system.maps {
+            let [x, y] = map.fixed_point().unwrap();
+            assert!((x - map.apply([x, y])[0]).abs() < 1e-12 && (y - map.apply([x, y])[1]).abs() < 1e-12);
+            assert!((min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y), "{}", system.name);
+        }
+    }
+
+    // Triangle (0, 0), (1, 0), (0.5, 1), with 1 % around it
+    let bounds = sierpinski().bounds();
+    for (bound, expected) in bounds.into_iter().zip([-0.01, -0.01, 1.01, 1.01]) {
+        assert!((bound - expected).abs() < 1e-3, "{:?}", bounds);
+    }
+}
+
+#[test]
+fn chaos_game_keeps_every_point_in_the_image() {
+    let points = 200_000;
+    for system in ifs::gallery() {
+        let histogram = system.chaos_game(SIZE, SIZE / 2, points, 3);
+        let total: u64 = histogram.counts.iter().map(|&count| count as u64).sum();
+        assert_eq!(total, points, "{}", system.name);
+    }
+}
+
+#[test]
+fn sierpinski_histogram_has_its_hole_and_equal_thirds() {
+    let points = 300_000;
+    let chaos = sierpinski().chaos_game(SIZE, SIZE, points, 9);
+    let deterministic = sierpinski().deterministic(SIZE, SIZE, 12);
+
+    // The view is centred on the unit square and gives 95 % of the image to
+    // its bounds, 1.02 wide
+    let scale = SIZE as f64 * 0.95 / 1.02;
+    let half = SIZE as f64 / 2.0;
+    let pixel = |x: f64, y: f64| [(half + (x - 0.5) * scale) as u32, (half - (y - 0.5) * scale) as u32];
+    for histogram in [&chaos, &deterministic] {
+        // Middle of the hole, then the three corners
+        assert_eq!(around(histogram, pixel(0.5, 0.25)), 0);
+        assert!(around(histogram, pixel(0.0, 0.0)) > 0);
+        assert!(around(histogram, pixel(1.0, 0.0)) > 0);
+        assert!(around(histogram, pixel(0.5, 1.0)) > 0);
+    }
+
+    // Each map carries a third of the points: the top half, then the left and
+    // right of the bottom half
+    let mut thirds = [0u64; 3];
+    for (index, &count) in chaos.counts.iter().enumerate() {
+        let (x, y) = (index as u32 % SIZE, index as u32 / SIZE);
+        let third = if y < SIZE / 2 { 0 } else if x < SIZE / 2 { 1 } else { 2 };
+        thirds[third] += count as u64;
+    }
+    for third in thirds {
+        let share = third as f64 / points as f64;
+        assert!((share - 1.0 / 3.0).abs() < 0.02, "{:?}", thirds);
+    }
+}
@@ -0,0 +1,78 @@
+use fractal_generation_rust::interval::Interval;
+use fractal_generation_rust::render::{Coloring, Style};
+use fractal_generation_rust::rule::Rule;
+use std::collections::BTreeMap;
+
+const SIZE: u32 = 729;
+const FILL: [u8; 4] = [0, 0, 0, 255];
+
+fn middle_thirds() -> Interval {
+    Interval::from_rule(&Rule::cantor())
+}
+
+fn style() -> Style {
+    Style {
+        background: [255, 255, 255, 255],
+        fill: FILL,
+        border: None,
+        coloring: Coloring::Uniform,
+        palette: Vec::new(),
+    }
+}
+
+#[test]
+fn staircase_rises_from_corner_to_corner() {
+    let (margin, width) = (SIZE as f64 * 0.05, SIZE as f64 * 0.9);
+    // Back to the unit square, `y` up
+    let unit = |[x, y]: [f64; 2]| [(x - margin) / width, 1.0 - (y - margin) / width];
+    for iterations in 1..=5 {
+        let points: Vec<_> = middle_thirds().staircase(SIZE, iterations).into_iter().map(unit).collect();
+        let (first, last) = (points[0], points[points.len() - 1]);
+        assert!(first[0].abs() < 1e-9 && first[1].abs() < 1e-9);
+        assert!((last[0] - 1.0).abs() < 1e-9 && (last[1] - 1.0).abs() < 1e-9);
+        for pair in points.windows(2) {
+            assert!(pair[1][0] >= pair[0][0] - 1e-9 && pair[1][1] >= pair[0][1] - 1e-9, "{:?}", pair);
+        }
+        // Flat at 1/2 over the middle third
+        let close = |p: [f64; 2], q: [f64; 2]| (p[0] - q[0]).abs() < 1e-9 && (p[1] - q[1]).abs() < 1e-9;
+        let flat = |pair: &[[f64; 2]]| close(pair[0], [1.0 / 3.0, 0.5]) && close(pair[1], [2.0 / 3.0, 0.5]);
+        assert!(points.windows(2).any(flat), "{:?}", points);
+    }
+}
+
+#[test]
+fn each_row_of_bars_doubles() {
+    for iterations in 0..=5 {
+        let mut rows = BTreeMap::new();
+        for bar in middle_thirds().bars(SIZE, iterations) {
+            *rows.entry(bar.y.to_bits()).or_insert(0u32) += 1;
+        }
+        let counts: Vec<u32> = rows.into_values().collect();
+        assert_eq!(counts, (0..=iterations).map(|level| 1 << level).collect::<Vec<_>>());
+    }
+    // Segments thinner than a pixel are not drawn: 3^6 > 729 × 0.9
+    assert_eq!(middle_thirds().bars(SIZE, 12).len(), middle_thirds().bars(SIZE, 5).len());
+}
+
+#[test]
+fn rendered_bars_show_every_segment() {
+    for iterations in 1..=4 {
+        let image = middle_thirds().render_bars(SIZE, iterations, &style());
+        let bars = middle_thirds().bars(SIZE, iterations);
+        let last = bars.last().unwrap();
+        let row = (last.y + last.height / 2.0) as u32;
+        let filled: Vec<bool> = (0..SIZE).map(|x| image.get_pixel(x, row).0 == FILL).collect();
+        let runs = filled.windows(2).filter(|pair| !pair[0] && pair[1]).count() + filled[0] as usize;
+        assert_eq!(runs, 1 << iterations);
+    }
+}
+
+#[test]
+fn rendered_staircase_joins_the_corners() {
+    let image = middle_thirds().render_staircase(SIZE, 5, &style());
+    let (low, high) = ((SIZE as f64 * 0.05) as u32, (SIZE as f64 * 0.95) as u32);
+    assert_eq!(image.get_pixel(low, high).0, FILL);
+    assert_eq!(image.get_pixel(high, low).0, FILL);
+    // The flat step at 1/2 crosses the middle of the image
+    assert_eq!(image.get_pixel(SIZE / 2, SIZE / 2).0, FILL);
+}
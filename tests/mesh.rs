@@ -0,0 +1,105 @@
+use fractal_generation_rust::mesh::Mesh;
+use fractal_generation_rust::solid::{MAX_MESH_CUBES, SolidRule};
+use std::fs;
+use std::path::PathBuf;
+
+/// Fresh directory for the files of one test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fracantor-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `(vertices, triangles)` of a binary STL, whose vertices are not shared.
+fn read_stl(bytes: &[u8]) -> (usize, usize) {
+    let triangles = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+    assert_eq!(bytes.len(), 84 + 50 * triangles);
+    (3 * triangles, triangles)
+}
+
+fn read_obj(text: &str) -> (usize, usize) {
+    let vertices = text.lines().filter(|line| line.starts_with("v ")).count();
+    let faces: Vec<Vec<usize>> = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("f "))
+        .map(|face| face.split_whitespace().map(|index| index.parse().unwrap()).collect())
+        .collect();
+    assert!(faces.iter().flatten().all(|&index| (1..=vertices).contains(&index)));
+    assert!(faces.iter().all(|face| face.len() == 3));
+    (vertices, faces.len())
+}
+
+fn read_ply(bytes: &[u8]) -> (usize, usize) {
+    const END: &[u8] = b"end_header\n";
+    let end = bytes.windows(END.len()).position(|window| window == END).unwrap() + END.len();
+    let header = std::str::from_utf8(&bytes[..end]).unwrap();
+    let count = |element: &str| -> usize {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("element {} ", element)))
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    let (vertices, faces) = (count("vertex"), count("face"));
+    // 3 floats per vertex, a count byte and 3 ints per face
+    assert_eq!(bytes.len() - end, 12 * vertices + 13 * faces);
+    let body = &bytes[end + 12 * vertices..];
+    for face in body.chunks_exact(13) {
+        assert_eq!(face[0], 3);
+        for index in face[1..].chunks_exact(4) {
+            assert!((i32::from_le_bytes(index.try_into().unwrap()) as usize) < vertices);
+        }
+    }
+    (vertices, faces)
+}
+
+/// Writes the mesh of `rule` in the three formats and reads the counts back.
+fn round_trip(name: &str, rule: &SolidRule, iterations: u32) -> [(usize, usize); 3] {
+    let dir = temp_dir(name);
+    let (mesh, _) = Mesh::from_voxels(&rule.voxels(iterations), 100.0);
+    for extension in ["stl", "obj", "ply"] {
+        mesh.save(dir.join(format!("{}.{}", name, extension)), name).unwrap();
+    }
+    let counts = [
+        read_stl(&fs::read(dir.join(format!("{}.stl", name))).unwrap()),
+        read_obj(&fs::read_to_string(dir.join(format!("{}.obj", name))).unwrap()),
+        read_ply(&fs::read(dir.join(format!("{}.ply", name))).unwrap()),
+    ];
+    fs::remove_dir_all(dir).unwrap();
+    counts
+}
+
+#[test]
+fn menger_sponge_level_one() {
+    // 20 cubes: the 72 outer faces of 144 triangles, on the 64 grid points
+    assert_eq!(round_trip("menger", &SolidRule::menger(), 1), [(432, 144), (64, 144), (64, 144)]);
+}
+
+#[test]
+fn cantor_dust_level_one() {
+    // 8 separate cubes, 12 triangles each, sharing no vertex
+    assert_eq!(round_trip("dust", &SolidRule::dust(), 1), [(288, 96), (64, 96), (64, 96)]);
+}
+
+#[test]
+fn single_cube() {
+    assert_eq!(round_trip("cube", &SolidRule::menger(), 0), [(36, 12), (8, 12), (8, 12)]);
+}
+
+#[test]
+fn unknown_extension_is_refused() {
+    let dir = temp_dir("extension");
+    let (mesh, _) = Mesh::from_voxels(&SolidRule::dust().voxels(1), 1.0);
+    assert!(mesh.save(dir.join("dust.txt"), "dust").is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_cube_budget_caps_the_depth() {
+    assert_eq!(SolidRule::menger().cube_limit(MAX_MESH_CUBES), 4);
+    assert_eq!(SolidRule::dust().cube_limit(MAX_MESH_CUBES), 6);
+    assert_eq!(SolidRule::menger().cube_limit(20), 1);
+    assert_eq!(SolidRule::menger().cube_limit(19), 0);
+}
@@ -0,0 +1,39 @@
+use fractal_generation_rust::format::{self, ImageOptions};
+use fractal_generation_rust::metadata::{self, Entries};
+use image::{Rgba, RgbaImage};
+use std::fs;
+
+/// Keys out of alphabetical order, one of them quoted in TOML.
+fn entries() -> Entries {
+    let mut entries = metadata::generator();
+    entries.extend([
+        metadata::entry("zoom", 2.5),
+        metadata::entry(metadata::RULE, "3:101000101"),
+        metadata::entry("couleur", "0,0,0,255"),
+        metadata::entry("a", "dernière"),
+    ]);
+    entries
+}
+
+#[test]
+fn text_keeps_the_order_of_the_entries() {
+    let text = metadata::to_text(&entries());
+    assert_eq!(metadata::from_text(&text).unwrap(), entries());
+}
+
+#[test]
+fn files_keep_the_order_of_the_entries() {
+    let dir = std::env::temp_dir().join(format!("fracantor-metadata-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let image = RgbaImage::from_pixel(9, 9, Rgba([10, 20, 30, 255]));
+    for extension in ["png", "jpg"] {
+        let path = dir.join(format!("ordre.{}", extension));
+        format::save(&image, &path, &entries(), &ImageOptions::default()).unwrap();
+        assert_eq!(metadata::read(&path).unwrap(), entries(), "{}", extension);
+    }
+    let path = dir.join("ordre.svg");
+    let document = svg::Document::new().add(metadata::svg_element(&entries()));
+    svg::save(&path, &document).unwrap();
+    assert_eq!(metadata::read(&path).unwrap(), entries());
+    fs::remove_dir_all(dir).unwrap();
+}
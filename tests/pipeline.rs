@@ -0,0 +1,41 @@
+use fractal_generation_rust::pipeline;
+use image::RgbaImage;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn frames_arrive_in_order_with_at_most_twice_the_lookahead_alive() {
+    for lookahead in [1, 2, 3, 8] {
+        let (alive, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let mut order = Vec::new();
+        pipeline::stream(
+            25,
+            lookahead,
+            |index| {
+                let now = alive.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                RgbaImage::new(index + 1, 1)
+            },
+            |index, frame| {
+                assert_eq!(frame.width(), index + 1);
+                order.push(index);
+                drop(frame);
+                alive.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(order, (0..25).collect::<Vec<_>>());
+        assert!(peak.into_inner() <= 2 * lookahead, "lookahead {}", lookahead);
+    }
+}
+
+#[test]
+fn a_consumer_error_stops_the_stream() {
+    let result = pipeline::stream(
+        100,
+        2,
+        |_| RgbaImage::new(1, 1),
+        |index, _| if index == 5 { Err("arrêt".into()) } else { Ok(()) },
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,83 @@
+use fractal_generation_rust::affine::Maps;
+use fractal_generation_rust::project::Project;
+use fractal_generation_rust::randomize::{self, Locks};
+
+fn start() -> Project {
+    Project {
+        maps: Maps::example(),
+        iterations: 4,
+        zoom: 2.0,
+        target: [0.25, 0.75],
+        ..Project::default()
+    }
+}
+
+#[test]
+fn a_seed_always_gives_the_same_project() {
+    let project = start();
+    for seed in 0..50 {
+        let roll = randomize::randomize(&project, seed, &Locks::default());
+        assert_eq!(roll, randomize::randomize(&project, seed, &Locks::default()));
+        assert_eq!(roll.seed, seed);
+        // Camera and export settings are never rolled
+        assert_eq!((&roll.camera, &roll.export), (&project.camera, &project.export));
+
+        let cells = roll.rule.grid * roll.rule.grid;
+        assert!((2..=5).contains(&roll.rule.grid));
+        assert!((2..cells).contains(&roll.rule.kept()));
+        assert!((2..=6).contains(&roll.iterations));
+        assert!(roll.maps.is_empty());
+    }
+    let rolls: Vec<_> = (0..10).map(|seed| randomize::randomize(&project, seed, &Locks::default()).rule).collect();
+    assert!(rolls.windows(2).any(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn locked_fields_stay_untouched() {
+    let project = start();
+    let all = Locks {
+        rule: true,
+        iterations: true,
+        palette: true,
+        coloring: true,
+        view: true,
+    };
+    for seed in 0..50 {
+        let roll = randomize::randomize(&project, seed, &all);
+        assert_eq!(Project { seed: project.seed, ..roll }, project);
+    }
+}
+
+#[test]
+fn locks_do_not_change_the_other_draws() {
+    let project = start();
+    for seed in 0..50 {
+        let free = randomize::randomize(&project, seed, &Locks::default());
+
+        let roll = randomize::randomize(&project, seed, &Locks { rule: true, ..Locks::default() });
+        assert_eq!((&roll.rule, &roll.maps), (&project.rule, &project.maps));
+        assert_eq!((roll.iterations, &roll.palette, roll.coloring), (free.iterations, &free.palette, free.coloring));
+
+        let roll = randomize::randomize(&project, seed, &Locks { iterations: true, ..Locks::default() });
+        assert_eq!(roll.iterations, project.iterations);
+        assert_eq!(Project { iterations: free.iterations, ..roll }, free);
+
+        let roll = randomize::randomize(&project, seed, &Locks { palette: true, ..Locks::default() });
+        assert_eq!((&roll.palette, roll.selected_color), (&project.palette, project.selected_color));
+        assert_eq!(
+            Project {
+                palette: free.palette.clone(),
+                selected_color: free.selected_color,
+                ..roll
+            },
+            free
+        );
+
+        let roll = randomize::randomize(&project, seed, &Locks { coloring: true, ..Locks::default() });
+        assert_eq!(Project { coloring: free.coloring, ..roll }, free);
+
+        let roll = randomize::randomize(&project, seed, &Locks { view: true, ..Locks::default() });
+        assert_eq!((roll.zoom, roll.target), (project.zoom, project.target));
+        assert_eq!(Project { zoom: free.zoom, target: free.target, ..roll }, free);
+    }
+}
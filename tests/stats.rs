@@ -0,0 +1,57 @@
+use fractal_generation_rust::affine::Maps;
+use fractal_generation_rust::rule::Rule;
+use fractal_generation_rust::stats::{self, Measures};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+}
+
+#[test]
+fn cantor_square_measures_follow_the_closed_forms() {
+    let rule = Rule::cantor();
+    for n in 0..12 {
+        let measures = Measures::new(&rule, n);
+        assert_eq!(measures.squares, Some(4u128.pow(n)));
+        assert!(close(measures.side, 3f64.powi(-(n as i32))));
+        assert!(close(measures.area, (4.0f64 / 9.0).powi(n as i32)), "aire {}", n);
+        // The corner squares never touch: 4^n squares of side 3^-n
+        assert!(close(measures.perimeter, 4.0 * (4.0f64 / 3.0).powi(n as i32)), "périmètre {}", n);
+        assert!(close(measures.dimension, 4f64.ln() / 3f64.ln()));
+    }
+    assert_eq!(Measures::new(&rule, 63).squares, Some(1 << 126));
+    assert_eq!(Measures::new(&rule, 64).squares, None);
+}
+
+#[test]
+fn shared_edges_leave_the_perimeter() {
+    // The full grid stays the unit square
+    let full: Rule = "3:111111111".parse().unwrap();
+    for n in 0..6 {
+        assert!(close(stats::perimeter(&full, n), 4.0));
+    }
+
+    // Sierpiński carpet: the outline, then the hole of each remaining square
+    let carpet: Rule = "3:111101111".parse().unwrap();
+    assert!(close(stats::perimeter(&carpet, 1), 4.0 + 4.0 / 3.0));
+    assert!(close(stats::perimeter(&carpet, 2), 4.0 + 4.0 / 3.0 + 8.0 * 4.0 / 9.0));
+    assert!(close(Measures::new(&carpet, 3).area, (8.0f64 / 9.0).powi(3)));
+}
+
+#[test]
+fn multi_scale_measures_sum_the_ratios() {
+    let maps = Maps::example();
+    let measures = Measures::from_maps(&maps, 3);
+    assert_eq!(measures.squares, Some(64));
+    assert!(close(measures.side, 0.25f64.powi(3)));
+    assert!(close(measures.area, (0.25 + 3.0 * 0.0625f64).powi(3)));
+    assert!(close(measures.perimeter, 4.0 * (0.5 + 3.0 * 0.25f64).powi(3)));
+}
+
+#[test]
+fn power_label_switches_to_scientific_notation() {
+    assert_eq!(stats::power_label(4, 0), "1");
+    assert_eq!(stats::power_label(4, 5), "1024");
+    assert_eq!(stats::power_label(2, 127), (1u128 << 127).to_string());
+    assert_eq!(stats::power_label(2, 128), "2^128 ≈ 3.403e38");
+    assert_eq!(stats::power_label(4, 100), "4^100 ≈ 1.607e60");
+}
@@ -0,0 +1,79 @@
+use fractal_generation_rust::project::Project;
+use fractal_generation_rust::render::{self, Coloring};
+use fractal_generation_rust::tiles::{self, TILE, Tiler};
+use image::{RgbaImage, imageops};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use tiff::ColorType;
+use tiff::decoder::{Decoder, DecodingResult};
+
+/// Not a multiple of [`TILE`]: the last row and column of tiles are cut.
+const SIZE: u32 = 300;
+
+/// Fresh directory for the files of one test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fracantor-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn project() -> Project {
+    let mut project = Project {
+        coloring: Coloring::Mosaic,
+        zoom: 1.5,
+        target: [0.2, 0.1],
+        ..Project::default()
+    };
+    project.iterations = project.pixel_depth(SIZE as f64);
+    project
+}
+
+/// The still view rendered in one piece.
+fn still(project: &Project) -> RgbaImage {
+    let (size, style) = (SIZE as usize, project.style());
+    let dust = project.dust();
+    let camera = project.still_camera();
+    render::render_rgba(&camera, size, size, &project.rule, dust.as_ref(), &project.maps, project.iterations, &style)
+}
+
+#[test]
+fn regions_line_up_with_the_still_view() {
+    let project = project();
+    let (tiler, still) = (Tiler::new(&project, SIZE).unwrap(), still(&project));
+    let colors: HashSet<_> = still.pixels().collect();
+    assert!(colors.len() > 2, "vue trop uniforme pour tester les raccords");
+    let regions = [(0, 0, SIZE, SIZE), (0, TILE, SIZE, SIZE - TILE), (17, 93, 101, 7), (299, 0, 1, 300)];
+    for (x, y, width, height) in regions {
+        let region = tiler.region(x, y, width, height);
+        assert_eq!(region, imageops::crop_imm(&still, x, y, width, height).to_image(), "{}, {}", x, y);
+    }
+}
+
+#[test]
+fn streamed_png_is_the_still_view() {
+    let (project, dir) = (project(), temp_dir("tiles-png"));
+    let path = dir.join("affiche.png");
+    tiles::export(&project, SIZE, path.to_str().unwrap(), |_| {}).unwrap();
+    assert_eq!(image::open(&path).unwrap().to_rgba8(), still(&project));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn bigtiff_tiles_decode_to_the_still_view() {
+    let (project, dir) = (project(), temp_dir("tiles-tiff"));
+    let path = dir.join("affiche.tif");
+    let mut steps = Vec::new();
+    tiles::export(&project, SIZE, path.to_str().unwrap(), |step| steps.push(step)).unwrap();
+    assert_eq!(steps.len(), SIZE.div_ceil(TILE) as usize);
+
+    let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (SIZE, SIZE));
+    assert_eq!(decoder.colortype().unwrap(), ColorType::RGBA(8));
+    let DecodingResult::U8(pixels) = decoder.read_image().unwrap() else {
+        panic!("TIFF 8 bits attendu");
+    };
+    assert_eq!(RgbaImage::from_raw(SIZE, SIZE, pixels).unwrap(), still(&project));
+    fs::remove_dir_all(dir).unwrap();
+}
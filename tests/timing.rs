@@ -0,0 +1,43 @@
+use fractal_generation_rust::camera::Easing;
+use fractal_generation_rust::timing::{self, Ticks, Timing};
+
+#[test]
+fn ticks_round_the_running_total() {
+    // 30 fps in GIF centiseconds
+    let mut ticks = Ticks::default();
+    let frames: Vec<u64> = (0..3).map(|_| ticks.advance(1000.0 / 30.0, 10.0)).collect();
+    assert_eq!(frames, [3, 4, 3]);
+    assert_eq!(frames.iter().sum::<u64>(), 10);
+
+    // No drift over a long animation
+    let mut ticks = Ticks::default();
+    let total: u64 = (0..3000).map(|_| ticks.advance(1000.0 / 30.0, 10.0)).sum();
+    assert_eq!(total, 10_000);
+}
+
+#[test]
+fn delays_parse_in_milliseconds_or_frame_rates() {
+    for value in ["40", "40ms", " 40 ms ", "25fps", "25 fps"] {
+        assert_eq!(timing::parse_delay(value), Ok(40.0), "{}", value);
+    }
+    assert_eq!(timing::parse_delay("0"), Ok(0.0));
+    for value in ["0fps", "-25fps", "-40", "40s", "vite", "", "inf"] {
+        assert!(timing::parse_delay(value).is_err(), "{}", value);
+    }
+}
+
+#[test]
+fn eased_delays_keep_the_total_duration() {
+    for easing in Easing::ALL {
+        let timing = Timing {
+            easing,
+            hold_first_ms: 500.0,
+            hold_last_ms: 250.0,
+            ..Timing::default()
+        };
+        let delays = timing.delays(20);
+        let total: f64 = delays.iter().sum();
+        assert!((total - (20.0 * 40.0 + 750.0)).abs() < 1e-3, "{}: {}", easing.label(), total);
+        assert!(delays.iter().all(|&delay| delay >= 0.0));
+    }
+}